@@ -329,3 +329,112 @@ mod bench10 {
 mod bench05 {
     bench_all!(5);
 }
+
+macro_rules! bench_insert_many {
+    ($n:literal) => {
+        use super::*;
+
+        fn entries() -> [(u64, u64); $n] {
+            let mut entries = [(0, 0); $n];
+
+            for (i, entry) in entries.iter_mut().enumerate() {
+                *entry = (i as u64 * 7919, i as u64);
+            }
+
+            entries
+        }
+
+        #[bench]
+        fn insert_many(b: &mut Bencher) {
+            let entries = entries();
+
+            b.iter(|| {
+                let mut map = Map::new();
+
+                map.insert_many(black_box(entries));
+
+                black_box(map);
+            });
+        }
+
+        #[bench]
+        fn insert_each(b: &mut Bencher) {
+            let entries = entries();
+
+            b.iter(|| {
+                let mut map = Map::new();
+
+                for (key, value) in black_box(entries) {
+                    map.insert(key, value);
+                }
+
+                black_box(map);
+            });
+        }
+    };
+}
+
+mod insert_many04 {
+    bench_insert_many!(4);
+}
+mod insert_many08 {
+    bench_insert_many!(8);
+}
+mod insert_many16 {
+    bench_insert_many!(16);
+}
+
+mod index100 {
+    use super::*;
+
+    #[bench]
+    fn x_index_u64(b: &mut Bencher) {
+        let map: Map<u64, u64> = (0..100u64).map(|x| (x * 7919, x)).collect();
+
+        b.iter(|| {
+            for i in 0..100u64 {
+                black_box(map.get(&(i * 7919)));
+            }
+        });
+    }
+}
+
+mod index10k {
+    use super::*;
+
+    #[bench]
+    fn x_index_u64(b: &mut Bencher) {
+        let mut map: Map<u64, u64> = Map::new();
+
+        // Inserted one by one, so the tree isn't balanced up front
+        for x in 0..10_000u64 {
+            map.insert(x * 7919, x);
+        }
+
+        b.iter(|| {
+            for i in (0..10_000u64).step_by(7) {
+                black_box(map.get(&(i * 7919)));
+            }
+        });
+    }
+}
+
+mod eq1k {
+    use super::*;
+
+    #[bench]
+    fn same_order(b: &mut Bencher) {
+        let a: Map<u64, u64> = (0..1000u64).map(|x| (x * 7919, x)).collect();
+        let c = a.clone();
+
+        b.iter(|| black_box(&a) == black_box(&c));
+    }
+
+    #[bench]
+    fn reverse_order(b: &mut Bencher) {
+        let a: Map<u64, u64> = (0..1000u64).map(|x| (x * 7919, x)).collect();
+        let c: Map<u64, u64> = (0..1000u64).rev().map(|x| (x * 7919, x)).collect();
+
+        b.iter(|| black_box(&a) == black_box(&c));
+    }
+}
@@ -0,0 +1,10 @@
+//! Looks up `str` keyed maps with the generic `Map::get`, for comparing
+//! code size with the `get_str_size` example, which explains how.
+
+macro_rules! lookup {
+    ($map:expr, $key:expr) => {
+        $map.get($key)
+    };
+}
+
+include!("size/lookups.rs");
@@ -0,0 +1,24 @@
+//! Looks up `str` keyed maps with `Map::get_str`, for comparing code size
+//! with the generic `Map::get` in the `get_size` example.
+//!
+//! Both do the same lookups on `Map<String, _>` and `Map<&str, _>`, with
+//! `&String`, `&str` and `&&str` probes. Build them optimized for size and
+//! compare the size of their `.text` sections:
+//!
+//! ```text
+//! export CARGO_PROFILE_RELEASE_OPT_LEVEL=s
+//! export CARGO_PROFILE_RELEASE_CODEGEN_UNITS=1
+//! export CARGO_PROFILE_RELEASE_PANIC=abort
+//!
+//! cargo build --release --example get_size --example get_str_size
+//! size -A target/release/examples/get_size | grep '^\.text'
+//! size -A target/release/examples/get_str_size | grep '^\.text'
+//! ```
+
+macro_rules! lookup {
+    ($map:expr, $key:expr) => {
+        $map.get_str(&$key[..])
+    };
+}
+
+include!("size/lookups.rs");
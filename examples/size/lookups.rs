@@ -0,0 +1,38 @@
+// Shared by the `get_size` and `get_str_size` examples, which only differ
+// in the `lookup!` macro they define before including this.
+
+use ordnung::Map;
+use std::hint::black_box;
+
+fn main() {
+    let mut a: Map<String, u32> = Map::new();
+    let mut b: Map<String, u64> = Map::new();
+    let mut c: Map<String, String> = Map::new();
+    let mut d: Map<&str, u32> = Map::new();
+
+    for i in 0..black_box(16u32) {
+        a.insert(i.to_string(), i);
+        b.insert(i.to_string(), i as u64);
+        c.insert(i.to_string(), i.to_string());
+    }
+
+    d.insert(black_box("4"), 4);
+
+    // Probes of every type a `str` keyed map can be looked up with
+    let owned = String::from("3");
+    let borrowed: &str = black_box("4");
+    let double: &&str = &borrowed;
+
+    let mut sum = 0u64;
+
+    sum += *lookup!(a, &owned).unwrap_or(&0) as u64;
+    sum += *lookup!(b, &owned).unwrap_or(&0);
+    sum += lookup!(c, &owned).map_or(0, |s| s.len() as u64);
+    sum += *lookup!(a, borrowed).unwrap_or(&0) as u64;
+    sum += *lookup!(b, borrowed).unwrap_or(&0);
+    sum += lookup!(c, borrowed).map_or(0, |s| s.len() as u64);
+    sum += *lookup!(d, double).unwrap_or(&0) as u64;
+    sum += *lookup!(d, borrowed).unwrap_or(&0) as u64;
+
+    println!("{}", sum);
+}
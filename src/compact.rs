@@ -1,6 +1,11 @@
 //! This is meant to be API compatible drop in replacement for std [`Vec<T>`](https://doc.rust-lang.org/std/vec/struct.Vec.html),
 //! but made compact by cramming capacity and length into a single 64bit word.
 //!
+//! Since length and capacity take 32 bits each, the vector holds at most
+//! `u32::MAX` elements. Anything that would grow it beyond that panics, or
+//! returns `TryReserveError::CapacityOverflow` for the fallible methods,
+//! where the standard `Vec` would only fail at `isize::MAX` bytes.
+//!
 //! ```rust
 //! use std::mem::size_of;
 //!
@@ -9,12 +14,14 @@
 //! assert_eq!(size_of::<Vec<u8>>(), WORD * 3);
 //! assert_eq!(size_of::<ordnung::compact::Vec<u8>>(), WORD * 2);
 //! ```
+pub use crate::error::TryReserveError;
 use alloc::vec::{IntoIter, Vec as StdVec};
 use core::fmt;
-use core::iter::FromIterator;
+use core::iter::{FromIterator, FusedIterator};
 use core::mem::ManuallyDrop;
-use core::ops::{Deref, DerefMut, Index, IndexMut};
+use core::ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds};
 use core::ptr::{slice_from_raw_parts, slice_from_raw_parts_mut, NonNull};
+use core::slice::SliceIndex;
 
 /// A contiguous growable array type, written `Vec<T>` but pronounced 'vector'.
 pub struct Vec<T> {
@@ -25,17 +32,52 @@ impl<T> Vec<T> {
     /// Constructs a new, empty Vec<T>.
     ///
     /// The vector will not allocate until elements are pushed onto it.
-    pub fn new() -> Self {
-        Self::from_stdvec_unchecked(StdVec::new())
+    pub const fn new() -> Self {
+        // Same as what `StdVec::new()` would be packed into: a dangling
+        // pointer, with no capacity unless `T` is zero-sized.
+        let cap = if core::mem::size_of::<T>() == 0 {
+            MASK_LO
+        } else {
+            0
+        };
+
+        Vec {
+            ptr: NonNull::slice_from_raw_parts(NonNull::dangling(), cap << 32),
+        }
     }
 
     /// Constructs a new, empty Vec<T> with the specified capacity.
     ///
     /// The vector will be able to hold exactly capacity elements without reallocating. If capacity is 0, the vector will not allocate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows a `u32`.
     pub fn with_capacity(capacity: usize) -> Self {
+        if capacity > MASK_LO {
+            panic!("compact Vec capacity out of bounds");
+        }
+
         Self::from_stdvec_unchecked(StdVec::with_capacity(capacity))
     }
 
+    /// Constructs a new, empty Vec<T> with the specified capacity, returning
+    /// an error instead of panicking or aborting if it overflows a `u32`,
+    /// or the memory can't be allocated.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        if capacity > MASK_LO {
+            return Err(TryReserveError::CapacityOverflow);
+        }
+
+        let mut stdvec = StdVec::new();
+
+        stdvec
+            .try_reserve_exact(capacity)
+            .map_err(|_| TryReserveError::AllocError)?;
+
+        Ok(Self::from_stdvec_unchecked(stdvec))
+    }
+
     /// Appends an element to the back of a collection.
     ///
     /// # Panics
@@ -74,6 +116,28 @@ impl<T> Vec<T> {
         }
     }
 
+    /// Shortens the vector, keeping the first `len` elements and dropping
+    /// the rest. Does nothing if `len` is greater than or equal to the
+    /// vector's current length.
+    ///
+    /// Note that this method has no effect on the allocated capacity of the vector.
+    pub fn truncate(&mut self, len: usize) {
+        let old_len = self.len();
+
+        if len >= old_len {
+            return;
+        }
+
+        unsafe {
+            // Shortened first, so that a panicking drop can't drop twice
+            self.set_len(len);
+
+            let tail = slice_from_raw_parts_mut(self.as_mut_ptr().add(len), old_len - len);
+
+            core::ptr::drop_in_place(tail);
+        }
+    }
+
     /// Removes the last element from a vector and returns it, or `None` if it is empty.
     pub fn pop(&mut self) -> Option<T> {
         let len = self.len().checked_sub(1)?;
@@ -97,6 +161,11 @@ impl<T> Vec<T> {
         len
     }
 
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns the number of elements the vector can hold without reallocating.
     pub fn capacity(&self) -> usize {
         let (_, cap) = self.parts();
@@ -104,12 +173,314 @@ impl<T> Vec<T> {
         cap
     }
 
+    /// Reserves capacity for at least `additional` more elements to be inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows a `u32`.
+    pub fn reserve(&mut self, additional: usize) {
+        let (len, cap) = self.parts();
+
+        if cap - len >= additional {
+            return;
+        }
+
+        let required = match len.checked_add(additional) {
+            Some(required) if required <= MASK_LO => required,
+            _ => panic!("compact Vec capacity out of bounds"),
+        };
+
+        // Amortized growth, same as `push`, without going out of bounds
+        let new_cap = required.max(cap * 2).min(MASK_LO);
+
+        self.with(move |v| v.reserve_exact(new_cap - len))
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to
+    /// be inserted, same as [`reserve`](#method.reserve), but returns an
+    /// error instead of panicking or aborting if the capacity would overflow
+    /// a `u32` or the allocator reports a failure.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let (len, cap) = self.parts();
+
+        if cap - len >= additional {
+            return Ok(());
+        }
+
+        let required = match len.checked_add(additional) {
+            Some(required) if required <= MASK_LO => required,
+            _ => return Err(TryReserveError::CapacityOverflow),
+        };
+
+        let new_cap = required.max(cap * 2).min(MASK_LO);
+
+        self.with(move |v| v.try_reserve_exact(new_cap - len))
+            .map_err(|_| TryReserveError::AllocError)
+    }
+
+    /// Reserves the minimum capacity for exactly `additional` more elements
+    /// to be inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows a `u32`.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        let (len, cap) = self.parts();
+
+        if cap - len >= additional {
+            return;
+        }
+
+        match len.checked_add(additional) {
+            Some(required) if required <= MASK_LO => {}
+            _ => panic!("compact Vec capacity out of bounds"),
+        }
+
+        self.with(move |v| v.reserve_exact(additional))
+    }
+
+    /// Shrinks the capacity of the vector as much as possible.
+    ///
+    /// Elements are never moved to different positions, only to a smaller buffer.
+    pub fn shrink_to_fit(&mut self) {
+        self.with(move |v| v.shrink_to_fit())
+    }
+
+    /// Shrinks the capacity of the vector with a lower bound.
+    ///
+    /// The capacity will remain at least as large as both the length and
+    /// `min_capacity`. Does nothing if the capacity is already smaller.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        if self.capacity() > min_capacity {
+            self.with(move |v| v.shrink_to(min_capacity))
+        }
+    }
+
+    /// Removes the specified range from the vector in bulk, returning all
+    /// removed elements as an iterator. Elements after the range are
+    /// shifted into place when the iterator is dropped.
+    ///
+    /// Elements not consumed by the iterator are dropped along with it.
+    /// Should the iterator be leaked, the vector is left truncated to the
+    /// start of the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end, or if
+    /// its end is greater than the length of the vector.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T>
+    where
+        R: RangeBounds<usize>,
+    {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+
+        assert!(start <= end, "drain range start is greater than its end");
+        assert!(end <= len, "drain range end out of bounds");
+
+        // Elements past `start` belong to the iterator until it's dropped
+        unsafe { self.set_len(start) };
+
+        Drain {
+            vec: self,
+            idx: start,
+            end,
+            tail_start: end,
+            tail_len: len - end,
+        }
+    }
+
+    /// Retains only the elements specified by the predicate, in place and
+    /// in order.
+    ///
+    /// Should the predicate panic, the elements it already rejected are
+    /// dropped, and all the others are kept.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|val| f(val))
+    }
+
+    /// Same as [`retain`](#method.retain), but passes a mutable reference
+    /// to each element to the predicate.
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        /// Closes the gap left by rejected elements, even if `f` panics.
+        struct Guard<'a, T> {
+            vec: &'a mut Vec<T>,
+            len: usize,
+            processed: usize,
+            deleted: usize,
+        }
+
+        impl<T> core::ops::Drop for Guard<'_, T> {
+            fn drop(&mut self) {
+                unsafe {
+                    let ptr = self.vec.as_mut_ptr();
+
+                    if self.deleted > 0 {
+                        core::ptr::copy(
+                            ptr.add(self.processed),
+                            ptr.add(self.processed - self.deleted),
+                            self.len - self.processed,
+                        );
+                    }
+
+                    self.vec.set_len(self.len - self.deleted);
+                }
+            }
+        }
+
+        let len = self.len();
+
+        // Elements are shuffled around out of sight until the guard is done
+        unsafe { self.set_len(0) };
+
+        let mut guard = Guard {
+            vec: self,
+            len,
+            processed: 0,
+            deleted: 0,
+        };
+
+        while guard.processed < len {
+            let cur = unsafe { &mut *guard.vec.as_mut_ptr().add(guard.processed) };
+
+            if !f(cur) {
+                guard.processed += 1;
+                guard.deleted += 1;
+
+                unsafe { core::ptr::drop_in_place(cur) };
+
+                continue;
+            }
+
+            if guard.deleted > 0 {
+                unsafe {
+                    let hole = guard.vec.as_mut_ptr().add(guard.processed - guard.deleted);
+
+                    core::ptr::copy_nonoverlapping(cur, hole, 1);
+                }
+            }
+
+            guard.processed += 1;
+        }
+    }
+
+    /// Splits the vector into two at the given index, returning a newly
+    /// allocated vector containing the elements in the range `[at, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        // Checked up front, a panic inside `with` would free the buffer
+        assert!(at <= self.len(), "split_off index out of bounds");
+
+        Self::from_stdvec_unchecked(self.with(move |v| v.split_off(at)))
+    }
+
+    /// Inserts an element at position `index` within the vector, shifting
+    /// all elements after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`, or if the number of elements in the vector
+    /// overflows a `u32`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        // Checked up front, a panic inside `with` would free the buffer
+        assert!(index <= self.len(), "insertion index out of bounds");
+
+        self.reserve(1);
+        self.with(move |v| v.insert(index, element))
+    }
+
     /// Removes and returns the element at position `index` within the vector,
     /// shifting all elements after it to the left.
     pub fn remove(&mut self, index: usize) -> T {
         self.with(move |v| v.remove(index))
     }
 
+    /// Removes an element from the vector and returns it, replacing it with
+    /// the last element. This doesn't preserve ordering, but is `O(1)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "swap_remove index out of bounds");
+
+        self.with(move |v| v.swap_remove(index))
+    }
+
+    /// Returns a reference to an element or subslice, without doing bounds
+    /// checking.
+    ///
+    /// Bounds are still checked in debug builds, so that an invalid index
+    /// turns into a panic instead of a read out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an out-of-bounds index is undefined behavior.
+    #[inline]
+    pub unsafe fn get_unchecked<I>(&self, index: I) -> &I::Output
+    where
+        I: SliceIndex<[T]> + Clone,
+    {
+        debug_assert!(
+            self.deref().get(index.clone()).is_some(),
+            "compact Vec index out of bounds"
+        );
+
+        self.deref().get_unchecked(index)
+    }
+
+    /// Returns a mutable reference to an element or subslice, without doing
+    /// bounds checking.
+    ///
+    /// Bounds are still checked in debug builds, so that an invalid index
+    /// turns into a panic instead of a write out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an out-of-bounds index is undefined behavior.
+    #[inline]
+    pub unsafe fn get_unchecked_mut<I>(&mut self, index: I) -> &mut I::Output
+    where
+        I: SliceIndex<[T]> + Clone,
+    {
+        debug_assert!(
+            self.deref().get(index.clone()).is_some(),
+            "compact Vec index out of bounds"
+        );
+
+        self.deref_mut().get_unchecked_mut(index)
+    }
+
+    /// Extracts a slice containing the entire vector.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    /// Extracts a mutable slice of the entire vector.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+
     /// Returns a raw pointer to the vector's buffer.
     #[inline]
     pub const fn as_ptr(&self) -> *const T {
@@ -122,7 +493,50 @@ impl<T> Vec<T> {
         self.ptr.cast().as_ptr()
     }
 
+    /// Decomposes the vector into its raw components: a pointer to the
+    /// buffer, its length and its capacity.
+    ///
+    /// The caller becomes responsible for the memory, which can be turned
+    /// back into a vector with [`from_raw_parts`](#method.from_raw_parts).
+    pub fn into_raw_parts(self) -> (*mut T, usize, usize) {
+        let mut vec = ManuallyDrop::new(self);
+        let (len, cap) = vec.parts();
+
+        (vec.as_mut_ptr(), len, cap)
+    }
+
+    /// Creates a vector directly from its raw components.
+    ///
+    /// # Safety
+    ///
+    /// Same as for the std `Vec::from_raw_parts`:
+    ///
+    /// + `ptr` must have been allocated with the global allocator, such as
+    ///   by [`into_raw_parts`](#method.into_raw_parts), or be dangling
+    ///   and well aligned if `capacity` is `0`.
+    /// + `T` must have the same size and alignment as what `ptr` was
+    ///   allocated with.
+    /// + `capacity` must be the capacity the pointer was allocated with.
+    /// + `length` must not exceed `capacity`, and the first `length`
+    ///   elements must be initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` overflows a `u32`.
+    pub unsafe fn from_raw_parts(ptr: *mut T, length: usize, capacity: usize) -> Self {
+        if capacity > MASK_LO {
+            panic!("compact Vec capacity out of bounds");
+        }
+
+        Self::from_stdvec_unchecked(StdVec::from_raw_parts(ptr, length, capacity))
+    }
+
     /// Sets the length
+    ///
+    /// # Safety
+    ///
+    /// `len` must be less than or equal to `capacity()`, and the elements
+    /// at `old_len..len` must be initialized.
     pub unsafe fn set_len(&mut self, len: usize) {
         let (_, cap) = self.parts();
 
@@ -143,7 +557,7 @@ impl<T> Vec<T> {
 
         let r = f(&mut stdvec);
 
-        ManuallyDrop::new(core::mem::replace(
+        core::mem::forget(core::mem::replace(
             self,
             Self::from_stdvec_unchecked(stdvec),
         ));
@@ -166,18 +580,115 @@ impl<T> Vec<T> {
     }
 }
 
-impl<T> Index<usize> for Vec<T> {
-    type Output = T;
+/// A draining iterator for `Vec<T>`, created by
+/// [`Vec::drain`](./struct.Vec.html#method.drain).
+pub struct Drain<'a, T> {
+    // The length of `vec` is set to the start of the drained range for the
+    // lifetime of the iterator, elements at `idx..end` are yet to be moved
+    // out, and `tail_len` elements from `tail_start` on are to be kept.
+    vec: &'a mut Vec<T>,
+    idx: usize,
+    end: usize,
+    tail_start: usize,
+    tail_len: usize,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.idx == self.end {
+            return None;
+        }
+
+        self.idx += 1;
+
+        // Every element is read at most once, and the vector won't touch
+        // it again, since it's past its length.
+        Some(unsafe { self.vec.as_ptr().add(self.idx - 1).read() })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
 
+impl<T> DoubleEndedIterator for Drain<'_, T> {
     #[inline]
-    fn index(&self, index: usize) -> &T {
+    fn next_back(&mut self) -> Option<T> {
+        if self.idx == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+
+        Some(unsafe { self.vec.as_ptr().add(self.end).read() })
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {
+    fn len(&self) -> usize {
+        self.end - self.idx
+    }
+}
+
+impl<T> FusedIterator for Drain<'_, T> {}
+
+impl<T> core::ops::Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        /// Shifts the tail into place, even if dropping an element panics.
+        struct MoveTail<'r, 'a, T>(&'r mut Drain<'a, T>);
+
+        impl<T> core::ops::Drop for MoveTail<'_, '_, T> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                let start = drain.vec.len();
+
+                unsafe {
+                    let ptr = drain.vec.as_mut_ptr();
+
+                    if drain.tail_start != start {
+                        core::ptr::copy(ptr.add(drain.tail_start), ptr.add(start), drain.tail_len);
+                    }
+
+                    drain.vec.set_len(start + drain.tail_len);
+                }
+            }
+        }
+
+        let remaining = slice_from_raw_parts_mut(
+            unsafe { self.vec.as_mut_ptr().add(self.idx) },
+            self.end - self.idx,
+        );
+
+        self.idx = self.end;
+
+        let _guard = MoveTail(self);
+
+        unsafe { core::ptr::drop_in_place(remaining) };
+    }
+}
+
+impl<T> Default for Vec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, I: SliceIndex<[T]>> Index<I> for Vec<T> {
+    type Output = I::Output;
+
+    #[inline]
+    fn index(&self, index: I) -> &I::Output {
         &self.deref()[index]
     }
 }
 
-impl<T> IndexMut<usize> for Vec<T> {
+impl<T, I: SliceIndex<[T]>> IndexMut<I> for Vec<T> {
     #[inline]
-    fn index_mut(&mut self, index: usize) -> &mut T {
+    fn index_mut(&mut self, index: I) -> &mut I::Output {
         &mut self.deref_mut()[index]
     }
 }
@@ -218,7 +729,23 @@ impl<T> From<Vec<T>> for StdVec<T> {
 
 impl<T: Clone> Clone for Vec<T> {
     fn clone(&self) -> Vec<T> {
-        Vec::from_stdvec_unchecked((&**self).to_vec())
+        Vec::from_stdvec_unchecked(self.to_vec())
+    }
+
+    /// Clones `source` into the existing buffer, only reallocating if the
+    /// capacity isn't sufficient.
+    fn clone_from(&mut self, source: &Self) {
+        self.truncate(source.len());
+        self.reserve(source.len() - self.len());
+
+        let (init, tail) = source.split_at(self.len());
+
+        // Cloning can panic, so stay clear of `with` here
+        self.clone_from_slice(init);
+
+        for val in tail {
+            self.push(val.clone());
+        }
     }
 }
 
@@ -229,7 +756,7 @@ impl<T> Deref for Vec<T> {
     fn deref(&self) -> &[T] {
         let (len, _) = self.parts();
 
-        unsafe { &*slice_from_raw_parts(self.as_ptr() as *mut T, len) }
+        unsafe { &*slice_from_raw_parts(self.as_ptr(), len) }
     }
 }
 
@@ -238,7 +765,7 @@ impl<T> DerefMut for Vec<T> {
     fn deref_mut(&mut self) -> &mut [T] {
         let (len, _) = self.parts();
 
-        unsafe { &mut *slice_from_raw_parts_mut(self.as_mut_ptr() as *mut T, len) }
+        unsafe { &mut *slice_from_raw_parts_mut(self.as_mut_ptr(), len) }
     }
 }
 
@@ -278,8 +805,8 @@ where
 unsafe impl<T: Sync> Sync for Vec<T> {}
 unsafe impl<T: Send> Send for Vec<T> {}
 
-const MASK_LO: usize = core::u32::MAX as usize;
-const MASK_HI: usize = !(core::u32::MAX as usize);
+const MASK_LO: usize = u32::MAX as usize;
+const MASK_HI: usize = !(u32::MAX as usize);
 
 #[inline]
 unsafe fn pack<T>(ptr: *mut T, len: usize, capacity: usize) -> NonNull<[T]> {
@@ -293,7 +820,7 @@ unsafe fn pack<T>(ptr: *mut T, len: usize, capacity: usize) -> NonNull<[T]> {
 #[inline]
 unsafe fn pack_unchecked<T>(ptr: *mut T, len: usize, capacity: usize) -> NonNull<[T]> {
     NonNull::new_unchecked(slice_from_raw_parts_mut(
-        ptr as *mut T,
+        ptr,
         (len & MASK_LO) | ((capacity & MASK_LO) << 32),
     ))
 }
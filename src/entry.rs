@@ -3,7 +3,7 @@
 //! their copyright.
 
 use super::*;
-use core::hash::{Hash, Hasher};
+use core::hash::{BuildHasher, Hash};
 
 use core::mem;
 // use std::fmt::{self, Debug};
@@ -16,18 +16,18 @@ use core::mem;
 ///
 /// [`HashMap`]: struct.HashMap.html
 /// [`entry`]: struct.HashMap.html#method.entry
-pub enum Entry<'a, K, V, H> {
+pub enum Entry<'a, K, V, S> {
     /// An occupied entry.
-    Occupied(OccupiedEntry<'a, K, V, H>),
+    Occupied(OccupiedEntry<'a, K, V, S>),
 
     /// A vacant entry.
-    Vacant(VacantEntry<'a, K, V, H>),
+    Vacant(VacantEntry<'a, K, V, S>),
 }
 
-impl<'a, K, V, H> Entry<'a, K, V, H>
+impl<'a, K, V, S> Entry<'a, K, V, S>
 where
     K: Clone,
-    H: Hasher + Default,
+    S: BuildHasher,
 {
     /// Ensures a value is in the entry by inserting the default if empty, and returns
     /// a mutable reference to the value in the entry.
@@ -82,6 +82,61 @@ where
         }
     }
 
+    /// Ensures a value is in the entry by inserting, if empty, the result of
+    /// the default function, which is given a reference to the key. Returns
+    /// a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<&str, usize> = Map::new();
+    ///
+    /// map.entry("poneyland").or_insert_with_key(|key| key.chars().count());
+    ///
+    /// assert_eq!(map["poneyland"], 9);
+    /// ```
+    #[inline]
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V
+    where
+        K: Eq + Hash,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<&str, Option<u32>> = Map::new();
+    /// map.entry("poneyland").or_default();
+    ///
+    /// assert_eq!(map["poneyland"], None);
+    /// ```
+    #[inline]
+    pub fn or_default(self) -> &'a mut V
+    where
+        K: Eq + Hash,
+        V: Default,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(V::default()),
+        }
+    }
+
     /// Returns a reference to this entry's key.
     ///
     /// # Examples
@@ -100,6 +155,36 @@ where
         }
     }
 
+    /// Returns the position of this entry in insertion order.
+    ///
+    /// For a vacant entry this is the position the entry will occupy once a
+    /// value is inserted through it, it is not a valid position in the map
+    /// before that.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<&str, u32> = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// let entry = map.entry("b");
+    /// let idx = entry.index();
+    /// entry.or_insert(2);
+    ///
+    /// assert_eq!(idx, 1);
+    /// assert_eq!(map.entry("a").index(), 0);
+    /// assert_eq!(map.entry("b").index(), 1);
+    /// ```
+    #[inline]
+    pub fn index(&self) -> usize {
+        match *self {
+            Entry::Occupied(ref entry) => entry.index(),
+            Entry::Vacant(ref entry) => entry.index(),
+        }
+    }
+
     /// Provides in-place mutable access to an occupied entry before any
     /// potential inserts into the map.
     ///
@@ -150,23 +235,10 @@ impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for Entry<'_, K, V, S> {
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-pub struct OccupiedEntry<'a, K, V, H> {
+pub struct OccupiedEntry<'a, K, V, S> {
     idx: usize,
     key: Option<K>,
-    map: &'a mut Map<K, V, H>,
-}
-
-unsafe impl<K, V, H> Send for OccupiedEntry<'_, K, V, H>
-where
-    K: Send,
-    V: Send,
-{
-}
-unsafe impl<K, V, H> Sync for OccupiedEntry<'_, K, V, H>
-where
-    K: Sync,
-    V: Sync,
-{
+    map: &'a mut Map<K, V, S>,
 }
 
 /*
@@ -180,11 +252,11 @@ impl<K: Debug, V: Debug, S> Debug for OccupiedEntry<'_, K, V, S> {
 }
 */
 
-impl<'a, K, V, H> OccupiedEntry<'a, K, V, H>
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
 where
     K: Clone,
 {
-    pub(crate) fn new(idx: usize, key: K, map: &'a mut Map<K, V, H>) -> Self {
+    pub(crate) fn new(idx: usize, key: K, map: &'a mut Map<K, V, S>) -> Self {
         Self {
             idx,
             key: Some(key),
@@ -192,6 +264,15 @@ where
         }
     }
 
+    /// Entry found without an owned key, see `Map::entry_ref`.
+    pub(crate) fn without_key(idx: usize, map: &'a mut Map<K, V, S>) -> Self {
+        Self {
+            idx,
+            key: None,
+            map,
+        }
+    }
+
     /// Gets a reference to the key in the entry.
     ///
     /// # Examples
@@ -208,6 +289,27 @@ where
         unsafe { &self.map.store.get_unchecked(self.idx).key }
     }
 
+    /// Returns the position of the entry in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    /// use ordnung::Entry;
+    ///
+    /// let mut map: Map<&str, u32> = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("poneyland", 12);
+    ///
+    /// if let Entry::Occupied(o) = map.entry("poneyland") {
+    ///     assert_eq!(o.index(), 1);
+    /// }
+    /// ```
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.map.position_of(self.idx)
+    }
+
     /// Take the ownership of the key and value from the map.
     ///
     /// # Examples
@@ -228,9 +330,7 @@ where
     /// ```
     #[inline]
     pub fn remove_entry(self) -> (K, V) {
-        let n = unsafe { self.map.store.get_unchecked_mut(self.idx) };
-
-        (n.key.clone(), n.value.take().unwrap())
+        self.map.remove_index(self.idx)
     }
 
     /// Gets a reference to the value in the entry.
@@ -380,6 +480,12 @@ where
     /// Replaces the entry, returning the old key and value. The new key in the hash map will be
     /// the key used to create this entry.
     ///
+    /// # Panics
+    ///
+    /// Panics if the entry was obtained through
+    /// [`entry_ref`](struct.Map.html#method.entry_ref), which doesn't take
+    /// an owned key.
+    ///
     /// # Examples
     ///
     /// ```
@@ -405,7 +511,7 @@ where
             ..
         } = unsafe { self.map.store.get_unchecked_mut(self.idx) }
         {
-            let old_key = mem::replace(cur_key, self.key.unwrap());
+            let old_key = mem::replace(cur_key, self.key.expect(NO_KEY));
             let old_value = mem::replace(cur_val, value);
 
             (old_key, old_value)
@@ -416,6 +522,12 @@ where
 
     /// Replaces the key in the hash map with the key used to create this entry.
     ///
+    /// # Panics
+    ///
+    /// Panics if the entry was obtained through
+    /// [`entry_ref`](struct.Map.html#method.entry_ref), which doesn't take
+    /// an owned key.
+    ///
     /// # Examples
     ///
     /// ```
@@ -441,17 +553,39 @@ where
     #[inline]
     pub fn replace_key(self) -> K {
         let key = unsafe { &mut self.map.store.get_unchecked_mut(self.idx).key };
-        mem::replace(key, self.key.unwrap())
+        mem::replace(key, self.key.expect(NO_KEY))
     }
 }
 
+const NO_KEY: &str = "entry obtained through entry_ref has no key to replace with";
+
 /// A view into a vacant entry in a `HashMap`.
 /// It is part of the [`Entry`] enum.
 ///
 /// [`Entry`]: enum.Entry.html
-pub struct VacantEntry<'a, K, V, H> {
+pub struct VacantEntry<'a, K, V, S> {
     key: K,
-    map: &'a mut Map<K, V, H>,
+    hash: u64,
+    // Link of the node the new entry will hang off, as found by `find`.
+    // It points into the store of `map`, which can't change while the
+    // entry holds the only reference to the map.
+    parent: Option<NonNull<Cell<Option<NonZeroU32>>>>,
+    map: &'a mut Map<K, V, S>,
+}
+
+unsafe impl<K, V, S> Send for VacantEntry<'_, K, V, S>
+where
+    K: Send,
+    V: Send,
+    S: Send,
+{
+}
+unsafe impl<K, V, S> Sync for VacantEntry<'_, K, V, S>
+where
+    K: Sync,
+    V: Sync,
+    S: Sync,
+{
 }
 
 /*
@@ -462,12 +596,22 @@ impl<K: Debug, V, S> Debug for VacantEntry<'_, K, V, S> {
 }
 */
 
-impl<'a, K, V, H> VacantEntry<'a, K, V, H>
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
 where
-    H: Hasher + Default,
+    S: BuildHasher,
 {
-    pub(crate) fn new(key: K, map: &'a mut Map<K, V, H>) -> Self {
-        Self { key, map }
+    pub(crate) fn new(
+        key: K,
+        hash: u64,
+        parent: Option<NonNull<Cell<Option<NonZeroU32>>>>,
+        map: &'a mut Map<K, V, S>,
+    ) -> Self {
+        Self {
+            key,
+            hash,
+            parent,
+            map,
+        }
     }
     /// Gets a reference to the key that would be used when inserting a value
     /// through the `VacantEntry`.
@@ -485,6 +629,29 @@ where
         &self.key
     }
 
+    /// Returns the position the entry will occupy in insertion order once
+    /// a value is inserted through it.
+    ///
+    /// Until then the map holds no entry at this position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    /// use ordnung::Entry;
+    ///
+    /// let mut map: Map<&str, u32> = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// if let Entry::Vacant(v) = map.entry("poneyland") {
+    ///     assert_eq!(v.index(), 1);
+    /// }
+    /// ```
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.map.len()
+    }
+
     /// Take ownership of the key.
     ///
     /// # Examples
@@ -526,7 +693,14 @@ where
         K: Eq + Hash,
     {
         let i = self.map.store.len();
-        self.map.insert(self.key, value);
+
+        if let Some(parent) = self.parent {
+            // Linked before the push, which may move the store
+            unsafe { parent.as_ref() }.set(link_to(i));
+        }
+
+        self.map.push_node(Node::new(self.key, value, self.hash));
+
         if let Node { value: Some(v), .. } = unsafe { self.map.store.get_unchecked_mut(i) } {
             v
         } else {
@@ -534,3 +708,246 @@ where
         }
     }
 }
+
+/// A view into a single entry in a map, which may either be vacant or
+/// occupied, looked up by a borrowed key.
+///
+/// This `enum` is constructed from the [`entry_ref`] method on [`Map`].
+///
+/// [`Map`]: struct.Map.html
+/// [`entry_ref`]: struct.Map.html#method.entry_ref
+pub enum EntryRef<'a, 'q, K, Q: ?Sized, V, S> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, S>),
+
+    /// A vacant entry.
+    Vacant(VacantEntryRef<'a, 'q, K, Q, V, S>),
+}
+
+impl<'a, K, Q, V, S> EntryRef<'a, '_, K, Q, V, S>
+where
+    K: Clone + Borrow<Q>,
+    Q: ToOwned<Owned = K> + ?Sized,
+    S: BuildHasher,
+{
+    /// Ensures a value is in the entry by inserting the default if empty, and returns
+    /// a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, u32> = Map::new();
+    ///
+    /// map.entry_ref("poneyland").or_insert(3);
+    /// assert_eq!(map["poneyland"], 3);
+    ///
+    /// *map.entry_ref("poneyland").or_insert(10) *= 2;
+    /// assert_eq!(map["poneyland"], 6);
+    /// ```
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a mut V
+    where
+        K: Eq + Hash,
+    {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, Vec<u32>> = Map::new();
+    ///
+    /// map.entry_ref("poneyland").or_insert_with(Vec::new).push(1);
+    ///
+    /// assert_eq!(map["poneyland"], [1]);
+    /// ```
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V
+    where
+        K: Eq + Hash,
+    {
+        match self {
+            EntryRef::Occupied(entry) => entry.into_mut(),
+            EntryRef::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, Option<u32>> = Map::new();
+    /// map.entry_ref("poneyland").or_default();
+    ///
+    /// assert_eq!(map["poneyland"], None);
+    /// ```
+    #[inline]
+    pub fn or_default(self) -> &'a mut V
+    where
+        K: Eq + Hash,
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Returns a reference to this entry's key, borrowed as `Q`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, u32> = Map::new();
+    /// assert_eq!(map.entry_ref("poneyland").key(), "poneyland");
+    /// ```
+    #[inline]
+    pub fn key(&self) -> &Q {
+        match *self {
+            EntryRef::Occupied(ref entry) => entry.key().borrow(),
+            EntryRef::Vacant(ref entry) => entry.key(),
+        }
+    }
+
+    /// Returns the position of this entry in insertion order, same as
+    /// [`Entry::index`](enum.Entry.html#method.index).
+    #[inline]
+    pub fn index(&self) -> usize {
+        match *self {
+            EntryRef::Occupied(ref entry) => entry.index(),
+            EntryRef::Vacant(ref entry) => entry.index(),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, u32> = Map::new();
+    ///
+    /// for _ in 0..2 {
+    ///     map.entry_ref("poneyland")
+    ///        .and_modify(|e| { *e += 1 })
+    ///        .or_insert(42);
+    /// }
+    ///
+    /// assert_eq!(map["poneyland"], 43);
+    /// ```
+    #[inline]
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            EntryRef::Occupied(mut entry) => {
+                f(entry.get_mut());
+                EntryRef::Occupied(entry)
+            }
+            EntryRef::Vacant(entry) => EntryRef::Vacant(entry),
+        }
+    }
+}
+
+/// A view into a vacant entry in a `Map`, looked up by a borrowed key.
+/// It is part of the [`EntryRef`] enum.
+///
+/// [`EntryRef`]: enum.EntryRef.html
+pub struct VacantEntryRef<'a, 'q, K, Q: ?Sized, V, S> {
+    key: &'q Q,
+    hash: u64,
+    // Same as in `VacantEntry`.
+    parent: Option<NonNull<Cell<Option<NonZeroU32>>>>,
+    map: &'a mut Map<K, V, S>,
+}
+
+unsafe impl<K, Q, V, S> Send for VacantEntryRef<'_, '_, K, Q, V, S>
+where
+    K: Send,
+    Q: Sync + ?Sized,
+    V: Send,
+    S: Send,
+{
+}
+unsafe impl<K, Q, V, S> Sync for VacantEntryRef<'_, '_, K, Q, V, S>
+where
+    K: Sync,
+    Q: Sync + ?Sized,
+    V: Sync,
+    S: Sync,
+{
+}
+
+impl<'a, 'q, K, Q, V, S> VacantEntryRef<'a, 'q, K, Q, V, S>
+where
+    Q: ?Sized,
+    S: BuildHasher,
+{
+    pub(crate) fn new(
+        key: &'q Q,
+        hash: u64,
+        parent: Option<NonNull<Cell<Option<NonZeroU32>>>>,
+        map: &'a mut Map<K, V, S>,
+    ) -> Self {
+        Self {
+            key,
+            hash,
+            parent,
+            map,
+        }
+    }
+
+    /// Gets a reference to the borrowed key the entry was looked up with.
+    #[inline]
+    pub fn key(&self) -> &'q Q {
+        self.key
+    }
+
+    /// Returns the position the entry will occupy in insertion order once
+    /// a value is inserted through it, same as
+    /// [`VacantEntry::index`](struct.VacantEntry.html#method.index).
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Converts the key into an owned one and sets the value of the entry
+    /// with it, returning a mutable reference to the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::{EntryRef, Map};
+    ///
+    /// let mut map: Map<String, u32> = Map::new();
+    ///
+    /// if let EntryRef::Vacant(v) = map.entry_ref("poneyland") {
+    ///     v.insert(37);
+    /// }
+    /// assert_eq!(map["poneyland"], 37);
+    /// ```
+    #[inline]
+    pub fn insert(self, value: V) -> &'a mut V
+    where
+        K: Eq + Hash,
+        Q: ToOwned<Owned = K>,
+    {
+        VacantEntry::new(self.key.to_owned(), self.hash, self.parent, self.map).insert(value)
+    }
+}
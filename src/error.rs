@@ -0,0 +1,164 @@
+//! Errors returned by the fallible operations on `Map`. None of them
+//! depend on `std`, so they are all available in `no_std` builds.
+
+use super::*;
+
+/// Error returned by `try_reserve` and `try_with_capacity` when the
+/// requested capacity can't be allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The capacity would exceed the maximum of `u32::MAX` elements.
+    CapacityOverflow,
+
+    /// The allocator failed to provide the memory.
+    AllocError,
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => f.write_str("capacity overflow"),
+            TryReserveError::AllocError => f.write_str("memory allocation failed"),
+        }
+    }
+}
+
+impl core::error::Error for TryReserveError {}
+
+/// Error returned by [`merge`](./struct.Map.html#method.merge) with
+/// `MergePolicy::Error` when a key is present in both maps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeError<K> {
+    /// The first incoming key that was already present in the map.
+    pub key: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for MergeError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key {:?} is present in both maps", self.key)
+    }
+}
+
+impl<K: fmt::Debug> core::error::Error for MergeError<K> {}
+
+/// Error returned by [`try_from_iter`](./struct.Map.html#method.try_from_iter)
+/// when the iterator yields the same key more than once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKeyError<K> {
+    /// The first key that was seen a second time.
+    pub key: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for DuplicateKeyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "duplicate key {:?}", self.key)
+    }
+}
+
+impl<K: fmt::Debug> core::error::Error for DuplicateKeyError<K> {}
+
+/// Error returned by [`validate`](./struct.Map.html#method.validate) when
+/// the tree linking the entries of a map is corrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The node at `index` links to a node past the end of the map.
+    LinkOutOfBounds {
+        /// Position of the node in the store, counting removed entries.
+        index: usize,
+    },
+
+    /// The node at `index` is on the wrong side of one of its ancestors.
+    OutOfOrder {
+        /// Position of the node in the store, counting removed entries.
+        index: usize,
+    },
+
+    /// The node at `index` is linked from more than one place.
+    LinkedTwice {
+        /// Position of the node in the store, counting removed entries.
+        index: usize,
+    },
+
+    /// The node at `index` can't be reached from the root.
+    Unreachable {
+        /// Position of the node in the store, counting removed entries.
+        index: usize,
+    },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegrityError::LinkOutOfBounds { index } => {
+                write!(f, "node {} links out of bounds", index)
+            }
+            IntegrityError::OutOfOrder { index } => write!(f, "node {} is out of order", index),
+            IntegrityError::LinkedTwice { index } => write!(f, "node {} is linked twice", index),
+            IntegrityError::Unreachable { index } => write!(f, "node {} is unreachable", index),
+        }
+    }
+}
+
+impl core::error::Error for IntegrityError {}
+
+/// Error returned by [`try_get`](./struct.Map.html#method.try_get) when the
+/// key is not present in the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyNotFound;
+
+impl fmt::Display for KeyNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("key not found in map")
+    }
+}
+
+impl core::error::Error for KeyNotFound {}
+
+/// The error returned by [`try_insert`] when the key already exists.
+///
+/// Contains the occupied entry, and the value that was not inserted.
+///
+/// [`try_insert`]: struct.Map.html#method.try_insert
+pub struct OccupiedError<'a, K, V, S> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, K, V, S>,
+    /// The value which was not inserted, because the entry was already occupied.
+    pub value: V,
+}
+
+impl<K, V, S> fmt::Debug for OccupiedError<'_, K, V, S>
+where
+    K: fmt::Debug + Clone,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<K, V, S> fmt::Display for OccupiedError<'_, K, V, S>
+where
+    K: fmt::Debug + Clone,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+impl<K, V, S> core::error::Error for OccupiedError<'_, K, V, S>
+where
+    K: fmt::Debug + Clone,
+    V: fmt::Debug,
+{
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+/// An immutable `Map` with a balanced tree, for maps that are built once
+/// and then only read from.
+///
+/// This struct is created by the [`freeze`](./struct.Map.html#method.freeze)
+/// method on [`Map`](./struct.Map.html). See its documentation for more.
+#[derive(Debug, Clone)]
+pub struct FrozenMap<K, V, S = DefaultHashBuilder> {
+    pub(crate) map: Map<K, V, S>,
+}
+
+impl<K, V, S> FrozenMap<K, V, S> {
+    /// Returns the number of elements in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// An iterator visiting all key-value pairs in insertion order.
+    /// The iterator element type is `(&K, &V)`.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.map.iter()
+    }
+}
+
+impl<K, V, S> FrozenMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+}
+
+impl<K, Q: ?Sized, V, S> Index<&Q> for FrozenMap<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// Returns a reference to the value corresponding to the supplied key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a FrozenMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
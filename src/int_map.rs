@@ -0,0 +1,87 @@
+use super::*;
+
+/// A `Map` for integer keys, hashing them with [`IntHasher`](./struct.IntHasher.html)
+/// instead of the general purpose default.
+///
+/// # Examples
+///
+/// ```rust
+/// use ordnung::IntMap;
+///
+/// let mut map = IntMap::default();
+/// map.insert(7u32, "seven");
+///
+/// assert_eq!(map[&7], "seven");
+/// ```
+pub type IntMap<K, V> = Map<K, V, BuildHasherDefault<IntHasher>>;
+
+/// A cheap hasher for integer keys, mixing them with a single rotate, xor
+/// and multiply per word, same as the one `rustc` uses, followed by a
+/// short xor-shift finalizer so that sequential keys don't produce
+/// sequential hashes.
+///
+/// Dense keys, like sequential IDs, still end up spread about as evenly as
+/// with the default hasher, but the hashes are trivially predictable,
+/// so don't use it for keys coming from untrusted input.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IntHasher {
+    hash: u64,
+}
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl IntHasher {
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for IntHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+
+            word[..chunk.len()].copy_from_slice(chunk);
+
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, n: u8) {
+        self.add_to_hash(n as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, n: u16) {
+        self.add_to_hash(n as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.add_to_hash(n as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.add_to_hash(n);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, n: usize) {
+        self.add_to_hash(n as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        let mut hash = self.hash;
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff_51_af_d7_ed_55_8c_cd);
+        hash ^= hash >> 33;
+
+        hash
+    }
+}
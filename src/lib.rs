@@ -10,10 +10,17 @@
 //! + Tree traversal is always breadth-first and happens over a single
 //!   continuous block of memory, which makes it cache friendly.
 //! + Iterating over all entries is always `O(n)`, same as `Vec<(K, V)>`.
-//! + Removing a value uses a sentinel and is `~O(log n)`.
+//! + Removing a value shifts all entries after it and rebuilds the tree,
+//!   which is `O(n)`. Entries can also be removed lazily, leaving them in
+//!   place until the map is compacted.
 //! + There are no buckets, so there is no need to re-bucket things when growing
 //!   the map.
 //!
+//! Entries are stored in a [`compact::Vec`](./compact/struct.Vec.html), which
+//! packs length and capacity into a single word. The `alloc-vec` feature
+//! swaps it for the standard `Vec`, making every map one word larger, for
+//! anyone who would rather rely on the standard type.
+//!
 //! ## When should you use this?
 //!
 //! + You need to preserve insertion order of the map.
@@ -22,36 +29,76 @@
 //! + You have no a priori knowledge about the final size of the map when you
 //!   start creating it.
 #![warn(missing_docs)]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 extern crate alloc;
 
+use alloc::borrow::ToOwned;
+use alloc::string::String;
 use core::{
     borrow::Borrow,
     cell::Cell,
     cmp::Ordering,
-    hash::{Hash, Hasher},
-    iter::FromIterator,
-    marker::PhantomData,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+    iter::{FromIterator, FusedIterator},
     num::NonZeroU32,
-    ops::Index,
+    ops::{Add, AddAssign, Bound, Index, IndexMut, RangeBounds},
+    ptr::NonNull,
     {fmt, slice},
 };
 
 pub mod compact;
 mod entry;
+mod error;
+mod frozen;
+mod int_map;
+mod macros;
+#[cfg(feature = "std")]
+pub mod persist;
 mod raw_entry;
+#[cfg(feature = "rayon")]
+mod rayon_impls;
+#[cfg(feature = "serde")]
+mod serde_impls;
 
 use ahash::AHasher;
 
 pub use compact::Vec;
 pub use entry::*;
+pub use error::*;
+pub use frozen::*;
+pub use int_map::*;
 pub use raw_entry::*;
-// use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+pub use serde_impls::SortedBy;
+
+/// The hash builder `Map` uses unless another one is given with
+/// `Map::with_hasher`.
+pub type DefaultHashBuilder = BuildHasherDefault<AHasher>;
+
+/// Storage of the nodes of a `Map`, see the `alloc-vec` feature.
+#[cfg(not(feature = "alloc-vec"))]
+type Store<T> = compact::Vec<T>;
+#[cfg(not(feature = "alloc-vec"))]
+type StoreDrain<'a, T> = compact::Drain<'a, T>;
+#[cfg(feature = "alloc-vec")]
+type Store<T> = alloc::vec::Vec<T>;
+#[cfg(feature = "alloc-vec")]
+type StoreDrain<'a, T> = alloc::vec::Drain<'a, T>;
 
 /// Iterator over the keys
 pub struct Keys<'a, K, V> {
     inner: Iter<'a, K, V>,
 }
+
+impl<K, V> Keys<'_, K, V> {
+    /// Create an empty iterator that always returns `None`
+    pub fn empty() -> Self {
+        Keys {
+            inner: Iter::empty(),
+        }
+    }
+}
+
 impl<'a, K, V> Iterator for Keys<'a, K, V> {
     type Item = &'a K;
 
@@ -65,11 +112,36 @@ impl<'a, K, V> Iterator for Keys<'a, K, V> {
     }
 }
 
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
 //#[derive(Clone)]
 /// Iterator over the values
 pub struct Values<'a, K, V> {
     inner: Iter<'a, K, V>,
 }
+
+impl<K, V> Values<'_, K, V> {
+    /// Create an empty iterator that always returns `None`
+    pub fn empty() -> Self {
+        Values {
+            inner: Iter::empty(),
+        }
+    }
+}
+
 impl<'a, K, V> Iterator for Values<'a, K, V> {
     type Item = &'a V;
 
@@ -83,6 +155,63 @@ impl<'a, K, V> Iterator for Values<'a, K, V> {
     }
 }
 
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+/// Mutable iterator over the values
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<K, V> ValuesMut<'_, K, V> {
+    /// Create an empty iterator that always returns `None`
+    pub fn empty() -> Self {
+        ValuesMut {
+            inner: IterMut::empty(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
 #[derive(Clone)]
 struct Node<K, V> {
     // Key
@@ -148,14 +277,21 @@ unsafe impl<K: Sync, V: Sync> Sync for Node<K, V> {}
 /// A `HashSet`-like type that preserves insertion order - this means O(n) iteration
 /// by insertion order and O(log(n)) lookup by key.
 #[derive(Debug, Clone)]
-pub struct Set<T, H = AHasher> {
-    map: Map<T, (), H>,
+pub struct Set<T, S = DefaultHashBuilder> {
+    map: Map<T, (), S>,
+}
+
+impl<T> Default for Set<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> Set<T> {
     /// Create a new `Set`
     #[inline]
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self { map: Map::new() }
     }
 
@@ -168,7 +304,7 @@ impl<T> Set<T> {
     }
 }
 
-impl<T, H> Set<T, H> {
+impl<T, S> Set<T, S> {
     /// Returns the number of elements in the set.
     #[inline]
     pub fn len(&self) -> usize {
@@ -211,10 +347,10 @@ impl<T, H> Set<T, H> {
     }
 }
 
-impl<T, H> Set<T, H>
+impl<T, S> Set<T, S>
 where
     T: Hash + Eq,
-    H: Hasher + Default,
+    S: BuildHasher,
 {
     /// Add a value to the set.
     ///
@@ -227,20 +363,20 @@ where
 
     /// Returns a reference to the value in the set, if any.
     #[inline]
-    pub fn get<Q: ?Sized>(&self, value: &Q) -> Option<&T>
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.map.get_key_value(value).map(|(k, _)| k)
     }
 
     /// Returns `true` if the set contains this value
     #[inline]
-    pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
+    pub fn contains<Q>(&self, value: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Eq + ?Sized,
     {
         self.map.contains_key(value)
     }
@@ -249,39 +385,47 @@ where
 // Because keys can inserted in different order, the safe way to
 // compare `Map`s is to iterate over one and check if the other
 // has all the same keys.
-impl<T> PartialEq for Set<T>
+impl<T, S> PartialEq for Set<T, S>
 where
-    T: PartialEq,
+    T: Hash + Eq,
+    S: BuildHasher,
 {
     fn eq(&self, other: &Self) -> bool {
         self.map == other.map
     }
 }
 
-impl<T> Eq for Set<T> where T: Eq {}
+impl<T, S> Eq for Set<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+}
 
 // Because keys can inserted in different order, the safe way to
 // compare `Map`s is to iterate over one and check if the other
 // has all the same keys.
-impl<T> PartialOrd for Set<T>
+impl<T, S> PartialOrd for Set<T, S>
 where
-    T: PartialOrd,
+    T: Ord + Hash,
+    S: BuildHasher,
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.map.partial_cmp(&other.map)
+        Some(self.cmp(other))
     }
 }
 
-impl<T> Ord for Set<T>
+impl<T, S> Ord for Set<T, S>
 where
-    T: Ord,
+    T: Ord + Hash,
+    S: BuildHasher,
 {
     fn cmp(&self, other: &Self) -> Ordering {
         self.map.cmp(&other.map)
     }
 }
 
-impl<T> IntoIterator for Set<T> {
+impl<T, S> IntoIterator for Set<T, S> {
     type Item = T;
     type IntoIter = SetIntoIter<T>;
 
@@ -291,7 +435,7 @@ impl<T> IntoIterator for Set<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a Set<T> {
+impl<'a, T, S> IntoIterator for &'a Set<T, S> {
     type Item = &'a T;
     type IntoIter = SetIter<'a, T>;
 
@@ -309,6 +453,8 @@ impl<T> ExactSizeIterator for SetIntoIter<T> {
     }
 }
 
+impl<T> FusedIterator for SetIntoIter<T> {}
+
 impl<T> DoubleEndedIterator for SetIntoIter<T> {
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
@@ -364,11 +510,145 @@ impl<T> ExactSizeIterator for SetIter<'_, T> {
     }
 }
 
+impl<T> FusedIterator for SetIter<'_, T> {}
+
 /// A `HashMap`-like type that preserves insertion order, implemented as a binary tree.
-#[derive(Debug, Clone)]
-pub struct Map<K, V, H = AHasher> {
-    store: Vec<Node<K, V>>,
-    hasher: PhantomData<H>,
+///
+/// Entries are linked with 32-bit indices, which limits a map to a little
+/// over 4 billion (`u32::MAX - 1`) entries. Inserting past that panics.
+pub struct Map<K, V, S = DefaultHashBuilder> {
+    // Nodes in insertion order. Node `0` is invariably the root of the tree,
+    // everything walking the tree starts there.
+    store: Store<Node<K, V>>,
+    // Number of nodes in the store that were removed with `remove_lazy`.
+    tombstones: usize,
+    // Whether `insert` balances the tree when it gets too deep.
+    auto_rebalance: bool,
+    // Whether the next `extend` balances the tree, set by
+    // `with_capacity_balanced` and cleared by that `extend`.
+    balance_on_extend: bool,
+    hasher: S,
+}
+
+/// Resolution of keys present in both maps when merging with
+/// [`merge`](./struct.Map.html#method.merge).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Keep the existing value, dropping the incoming one.
+    KeepExisting,
+
+    /// Replace the existing value with the incoming one.
+    TakeIncoming,
+
+    /// Abort the merge without modifying the map.
+    Error,
+}
+
+/// Cost of removing an entry with [`remove`](./struct.Map.html#method.remove),
+/// as returned by [`remove_cost_hint`](./struct.Map.html#method.remove_cost_hint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalKind {
+    /// The entry is only unlinked from the tree, same cost as a lookup.
+    Cheap,
+
+    /// The entries following it shift, and the whole tree is rebuilt.
+    Rebuild,
+}
+
+/// Link to the node at store index `idx`, which is about to be pushed.
+///
+/// # Panics
+///
+/// Panics if `idx` doesn't fit in a link, instead of silently truncating it
+/// and corrupting the tree.
+#[inline]
+fn link_to(idx: usize) -> Option<NonZeroU32> {
+    if idx >= u32::MAX as usize {
+        panic!("ordnung: map exceeds maximum of u32::MAX entries");
+    }
+
+    NonZeroU32::new(idx as u32)
+}
+
+/// Hints the CPU to start loading both children of `node`, so that fetching
+/// the next node overlaps with comparing the key of this one. Does nothing
+/// on targets other than `x86_64` and `aarch64`.
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+fn prefetch_children<K, V>(store: &[Node<K, V>], node: &Node<K, V>) {
+    for link in [&node.left, &node.right].iter() {
+        if let Some(idx) = link.get() {
+            // Never dereferenced, a bad link is caught by the lookup itself
+            let ptr = store.as_ptr().wrapping_add(idx.get() as usize) as *const i8;
+
+            #[cfg(target_arch = "x86_64")]
+            unsafe {
+                use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+                _mm_prefetch::<_MM_HINT_T0>(ptr);
+            }
+
+            #[cfg(target_arch = "aarch64")]
+            unsafe {
+                core::arch::asm!(
+                    "prfm pldl1keep, [{}]",
+                    in(reg) ptr,
+                    options(nostack, readonly, preserves_flags),
+                );
+            }
+
+            #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+            let _ = ptr;
+        }
+    }
+}
+
+/// Resolve `range` into `start..end` store positions, clamped to `len`.
+fn clamp_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    let end = end.min(len);
+
+    (start.min(end), end)
+}
+
+/// Resolve `range` into `start..end` positions, panicking if it's out of
+/// bounds of `len`, same as slice indexing does.
+fn check_range<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_add(1)
+            .expect("attempted to index map from after maximum usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_add(1)
+            .expect("attempted to index map up to maximum usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    if start > end {
+        panic!("map index starts at {} but ends at {}", start, end);
+    }
+    if end > len {
+        panic!(
+            "range end index {} out of range for map of length {}",
+            end, len
+        );
+    }
+
+    (start, end)
 }
 
 enum FindResult<'find> {
@@ -380,180 +660,207 @@ use FindResult::*;
 
 impl<K, V> Map<K, V> {
     /// Create a new `Map`.
+    ///
+    /// This doesn't allocate, and can be used to initialize a `static` or
+    /// `const` map.
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// static EMPTY: Map<&str, u32> = Map::new();
+    ///
+    /// assert!(EMPTY.is_empty());
+    /// assert_eq!(EMPTY.get("a"), None);
+    /// ```
     #[inline]
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Create a `Map` with a given capacity
-    #[inline]
-    pub fn with_capacity(capacity: usize) -> Self {
+    pub const fn new() -> Self {
         Map {
-            store: Vec::with_capacity(capacity),
-            hasher: PhantomData,
+            store: Store::new(),
+            tombstones: 0,
+            auto_rebalance: false,
+            balance_on_extend: false,
+            hasher: BuildHasherDefault::new(),
         }
     }
-}
 
-impl<K, V, H> Map<K, V, H> {
-    /// Returns the number of elements in the map.
+    /// Create a `Map` with a given capacity
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows a `u32`, since no more entries than
+    /// that can be linked in the tree anyway.
     #[inline]
-    pub fn len(&self) -> usize {
-        self.store.len()
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
     }
 
-    /// Returns `true` if the map contains no elements.
+    /// Create a `Map` with room for exactly `capacity` entries, meant to be
+    /// filled in bulk with [`extend`], with the tree balanced once they are
+    /// all in.
+    ///
+    /// Only the first `extend` balances the tree, unconditionally, after its
+    /// last entry. Unlike [automatic
+    /// rebalancing](#method.set_auto_rebalance), nothing is checked on any
+    /// later insert.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows a `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = Map::with_capacity_balanced(1000);
+    /// map.extend((0..1000u32).map(|x| (x, x)));
+    ///
+    /// assert_eq!(map.capacity(), 1000);
+    /// assert!(map.is_balanced(1.0));
+    /// assert!(!map.auto_rebalance());
+    /// ```
+    ///
+    /// [`extend`]: https://doc.rust-lang.org/std/iter/trait.Extend.html#tymethod.extend
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.store.is_empty()
-    }
+    pub fn with_capacity_balanced(capacity: usize) -> Self {
+        let mut map = Self::with_capacity(capacity);
 
-    /// Clears the map, removing all key-value pairs. Keeps the allocated memory for reuse.
-    #[inline]
-    pub fn clear(&mut self) {
-        self.store.clear();
+        map.balance_on_extend = true;
+        map
     }
 
-    /// An iterator visiting all key-value pairs in insertion order.
-    /// The iterator element type is `(&K, &V)`.
+    /// Create a `Map` with a given capacity, same as
+    /// [`with_capacity`](#method.with_capacity), but returns an error instead
+    /// of panicking or aborting if the capacity overflows a `u32` or the
+    /// memory can't be allocated.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// use ordnung::Map;
-    ///
-    /// let mut map = Map::new();
-    /// map.insert("a", 1);
-    /// map.insert("b", 2);
-    /// map.insert("c", 3);
+    /// use ordnung::{Map, TryReserveError};
     ///
-    /// let entries: Vec<_> = map.iter().collect();
+    /// let map: Map<&str, i32> = Map::try_with_capacity(10).unwrap();
+    /// assert!(map.capacity() >= 10);
     ///
-    /// assert_eq!(
-    ///     entries,
-    ///     &[
-    ///         (&"a", &1),
-    ///         (&"b", &2),
-    ///         (&"c", &3),
-    ///     ],
-    /// );
+    /// let err = Map::<&str, i32>::try_with_capacity(u32::MAX as usize + 1).unwrap_err();
+    /// assert_eq!(err, TryReserveError::CapacityOverflow);
     /// ```
     #[inline]
-    pub fn iter(&self) -> Iter<K, V> {
-        Iter {
-            inner: self.store.iter(),
-        }
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        let mut map = Self::new();
+
+        map.try_reserve(capacity)?;
+
+        Ok(map)
     }
+}
 
-    /// An iterator visiting all key-value pairs in insertion order, with
-    /// mutable references to the values. The iterator element type is
-    /// (&K, &mut V).
+impl<K, V, S> Map<K, V, S> {
+    /// Create a new `Map` which will use the given hash builder to hash keys.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use ordnung::Map;
+    /// use std::collections::hash_map::RandomState;
     ///
-    /// let mut map = Map::new();
+    /// let mut map = Map::with_hasher(RandomState::new());
     /// map.insert("a", 1);
-    /// map.insert("b", 2);
-    /// map.insert("c", 3);
-    ///
-    /// // Update all values
-    /// for (_, val) in map.iter_mut() {
-    ///     *val *= 2;
-    /// }
-    ///
-    /// // Check if values are doubled
-    /// let entries: Vec<_> = map.iter().collect();
     ///
-    /// assert_eq!(
-    ///     entries,
-    ///     &[
-    ///         (&"a", &2),
-    ///         (&"b", &4),
-    ///         (&"c", &6),
-    ///     ],
-    /// );
+    /// assert_eq!(map["a"], 1);
     /// ```
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<K, V> {
-        IterMut {
-            inner: self.store.iter_mut(),
-        }
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::with_capacity_and_hasher(0, hasher)
     }
-}
 
-impl<K, V, H> Default for Map<K, V, H> {
-    /// Create a new `Map` with a custom hasher.
+    /// Create a `Map` with a given capacity, which will use the given hash
+    /// builder to hash keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows a `u32`.
     #[inline]
-    fn default() -> Self {
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
         Map {
-            store: Vec::new(),
-            hasher: PhantomData,
+            store: Store::with_capacity(capacity),
+            tombstones: 0,
+            auto_rebalance: false,
+            balance_on_extend: false,
+            hasher,
         }
     }
-}
 
-impl<K, V, H> Map<K, V, H>
-where
-    K: Hash + Eq,
-    H: Hasher + Default,
-{
-    /// An iterator visiting all keys in arbitrary order.
-    /// The iterator element type is `&'a K`.
-    pub fn keys(&self) -> Keys<'_, K, V> {
-        Keys { inner: self.iter() }
+    /// Returns a reference to the map's hash builder.
+    #[inline]
+    pub fn hasher(&self) -> &S {
+        &self.hasher
     }
 
-    /// An iterator visiting all values in arbitrary order.
-    /// The iterator element type is `&'a V`.
-    pub fn values(&self) -> Values<'_, K, V> {
-        Values { inner: self.iter() }
+    /// Returns the number of elements in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.store.len() - self.tombstones
     }
 
-    /// Inserts a key-value pair into the map.
-    ///
-    /// If the map did not have this key present, `None` is returned.
+    /// Returns `true` if the map contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries removed with
+    /// [`remove_lazy`](#method.remove_lazy) that still take up space in
+    /// the map, until it is [`compact`](#method.compact)ed.
+    #[inline]
+    pub fn tombstone_count(&self) -> usize {
+        self.tombstones
+    }
+
+    /// Drops all entries removed with [`remove_lazy`](#method.remove_lazy)
+    /// from the storage, rebuilding the tree if there were any.
     ///
-    /// If the map did have this key present, the value is updated, and the old
-    /// value is returned. The key is not updated, though; this matters for
-    /// types that can be `==` without being identical.
+    /// This is `O(n)`, same as a single [`remove`](#method.remove).
     ///
     /// # Examples
     ///
     /// ```rust
     /// use ordnung::Map;
     ///
-    /// let mut map = Map::new();
-    /// assert_eq!(map.insert(37, "a"), None);
-    /// assert_eq!(map.is_empty(), false);
+    /// let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
     ///
-    /// map.insert(37, "b");
-    /// assert_eq!(map.insert(37, "c"), Some("b"));
-    /// assert_eq!(map[&37], "c");
+    /// map.remove_lazy(&3);
+    /// map.remove_lazy(&7);
+    /// assert_eq!(map.tombstone_count(), 2);
+    ///
+    /// map.compact();
+    /// assert_eq!(map.tombstone_count(), 0);
+    /// assert_eq!(map.len(), 8);
+    /// assert_eq!(map.get(&4), Some(&4));
     /// ```
-    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        let hash = Self::hash_key(&key);
-
-        match self.find(hash) {
-            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.replace(value) },
-            Miss(parent) => {
-                if let Some(parent) = parent {
-                    parent.set(NonZeroU32::new(self.store.len() as u32));
-                }
+    pub fn compact(&mut self) {
+        if self.tombstones == 0 {
+            return;
+        }
 
-                self.store.push(Node::new(key, value, hash));
+        let old = core::mem::take(&mut self.store);
+        let mut store = Store::with_capacity(old.capacity());
 
-                None
+        for node in old {
+            if node.value.is_some() {
+                store.push(node);
             }
         }
+
+        self.store = store;
+        self.tombstones = 0;
+        self.rebuild_tree();
     }
 
-    /// Returns a reference to the value corresponding to the key.
+    /// Sorts the entries of the map by key, changing the iteration order.
     ///
-    /// The key may be any borrowed form of the map's key type, but `Hash` and
-    /// `Eq` on the borrowed form must match those for the key type.
+    /// The sort is stable, and the tree is rebuilt afterwards, which makes
+    /// this `O(n log n)`.
     ///
     /// # Examples
     ///
@@ -561,54 +868,67 @@ where
     /// use ordnung::Map;
     ///
     /// let mut map = Map::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.get(&1), Some(&"a"));
-    /// assert_eq!(map.get(&2), None);
-    /// ```
-    pub fn get<Q>(&self, key: &Q) -> Option<&V>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    /// map.insert("a", 1);
+    ///
+    /// map.sort_keys();
+    ///
+    /// assert!(map.keys().eq(&["a", "b", "c"]));
+    /// assert_eq!(map["c"], 3);
+    /// ```
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
     {
-        self.get_key_value(key).map(|(_, v)| v)
+        self.sort_by(|k1, _, k2, _| k1.cmp(k2));
     }
 
-    /// Returns a reference to the value corresponding to the key, along with the original key.
+    /// Puts the map in a canonical form: entries removed with
+    /// [`remove_lazy`](#method.remove_lazy) are dropped, and the rest are
+    /// sorted by key, same as with [`sort_keys`](#method.sort_keys).
     ///
-    /// The key may be any borrowed form of the map's key type, but `Hash` and
-    /// `Eq` on the borrowed form must match those for the key type.
+    /// Two maps that are equal, and use the same hasher, iterate,
+    /// serialize and compare with [`eq_ordered`](#method.eq_ordered)
+    /// identically after being canonicalized, regardless of the order
+    /// their entries were inserted in.
     ///
     /// # Examples
     ///
     /// ```rust
     /// use ordnung::Map;
     ///
-    /// let mut map = Map::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.get(&1), Some(&"a"));
-    /// assert_eq!(map.get(&2), None);
+    /// let mut a = Map::new();
+    /// a.insert("x", 1);
+    /// a.insert("y", 2);
+    ///
+    /// let mut b = Map::new();
+    /// b.insert("y", 2);
+    /// b.insert("z", 3);
+    /// b.insert("x", 1);
+    /// b.remove_lazy("z");
+    ///
+    /// assert!(!a.eq_ordered(&b));
+    ///
+    /// a.canonicalize();
+    /// b.canonicalize();
+    ///
+    /// assert!(a.eq_ordered(&b));
     /// ```
-    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    pub fn canonicalize(&mut self)
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
+        K: Ord,
     {
-        let hash = Self::hash_key(key);
-
-        match self.find(hash) {
-            Hit(idx) => {
-                let node = unsafe { self.store.get_unchecked(idx) };
-
-                node.value.as_ref().map(|v| (&node.key, v))
-            }
-            Miss(_) => None,
-        }
+        self.compact();
+        self.sort_keys();
     }
 
-    /// Returns a mutable reference to the value corresponding to the key.
+    /// Sorts the entries of the map with a comparator function, changing
+    /// the iteration order.
     ///
-    /// The key may be any borrowed form of the map's key type, but Hash and Eq
-    /// on the borrowed form must match those for the key type.
+    /// The comparator receives the key and value of both entries being
+    /// compared. The sort is stable, and the tree is rebuilt afterwards,
+    /// which makes this `O(n log n)`.
     ///
     /// # Examples
     ///
@@ -616,29 +936,32 @@ where
     /// use ordnung::Map;
     ///
     /// let mut map = Map::new();
-    /// map.insert(1, "a");
-    /// if let Some(x) = map.get_mut(&1) {
-    ///     *x = "b";
-    /// }
-    /// assert_eq!(map[&1], "b");
+    /// map.insert("a", 3);
+    /// map.insert("b", 1);
+    /// map.insert("c", 2);
+    ///
+    /// map.sort_by(|_, v1, _, v2| v1.cmp(v2));
+    ///
+    /// assert!(map.keys().eq(&["b", "c", "a"]));
     /// ```
-    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    pub fn sort_by<F>(&mut self, mut cmp: F)
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
     {
-        let hash = Self::hash_key(key);
-
-        match self.find(hash) {
-            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.as_mut() },
-            Miss(_) => None,
-        }
+        self.compact();
+
+        self.reorder(|store| {
+            store.sort_by(|a, b| match (&a.value, &b.value) {
+                (Some(v1), Some(v2)) => cmp(&a.key, v1, &b.key, v2),
+                _ => unreachable!("Map was just compacted"),
+            })
+        });
     }
 
-    /// Returns `true` if the map contains a value for the specified key.
+    /// Reverses the order of entries in the map.
     ///
-    /// The key may be any borrowed form of the map's key type, but `Hash` and
-    /// `Eq` on the borrowed form must match those for the key type.
+    /// The tree is rebuilt afterwards, same as with
+    /// [`remove`](#method.remove), which makes this `O(n)`.
     ///
     /// # Examples
     ///
@@ -646,61 +969,79 @@ where
     /// use ordnung::Map;
     ///
     /// let mut map = Map::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.contains_key(&1), true);
-    /// assert_eq!(map.contains_key(&2), false);
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// map.reverse();
+    ///
+    /// assert!(map.keys().eq(&["c", "b", "a"]));
+    /// assert_eq!(map["a"], 1);
     /// ```
-    pub fn contains_key<Q>(&self, key: &Q) -> bool
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = Self::hash_key(key);
-
-        match self.find(hash) {
-            Hit(idx) => unsafe { self.store.get_unchecked(idx).value.is_some() },
-            Miss(_) => false,
-        }
+    pub fn reverse(&mut self) {
+        self.reorder(|store| store.reverse());
     }
 
-    /// Get a mutable reference to entry at key. Inserts a new entry by
-    /// calling `F` if absent.
-    // TODO: Replace with entry API
-    pub fn get_or_insert<F>(&mut self, key: K, fill: F) -> &mut V
+    /// Splits the map in two at the given position in insertion order,
+    /// returning a new map with all entries from `at` onwards, and leaving
+    /// the entries before `at` in this map.
+    ///
+    /// The trees of both maps are rebuilt, same as with
+    /// [`remove`](#method.remove), which makes this `O(n)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// let tail = map.split_off(1);
+    ///
+    /// assert!(map.keys().eq(&["a"]));
+    /// assert!(tail.keys().eq(&["b", "c"]));
+    /// assert_eq!(tail["c"], 3);
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> Map<K, V, S>
     where
-        F: FnOnce() -> V,
+        S: Clone,
     {
-        let hash = Self::hash_key(&key);
-
-        match self.find(hash) {
-            Hit(idx) => {
-                let node = unsafe { self.store.get_unchecked_mut(idx) };
-
-                if node.value.is_none() {
-                    node.value = Some(fill());
-                }
+        assert!(at <= self.len(), "split index out of bounds");
 
-                node.value.as_mut().unwrap()
-            }
-            Miss(parent) => {
-                let idx = self.store.len();
+        let store = self.store.split_off(self.store_index(at));
+        let tombstones = store.iter().filter(|node| node.value.is_none()).count();
 
-                if let Some(parent) = parent {
-                    parent.set(NonZeroU32::new(self.store.len() as u32));
-                }
+        self.tombstones -= tombstones;
+        self.rebuild_tree();
 
-                self.store.push(Node::new(key, fill(), hash));
+        let mut tail = Map {
+            store,
+            tombstones,
+            auto_rebalance: self.auto_rebalance,
+            balance_on_extend: false,
+            hasher: self.hasher.clone(),
+        };
 
-                self.store[idx].value.as_mut().unwrap()
-            }
-        }
+        tail.rebuild_tree();
+        tail
     }
 
-    /// Removes a key from the map, returning the value at the key if the key
-    /// was previously in the map.
+    /// Shortens the map, keeping the first `len` entries in insertion order
+    /// and dropping the rest. Does nothing if `len` is greater than or
+    /// equal to the map's current length.
     ///
-    /// The key may be any borrowed form of the map's key type, but `Hash` and
-    /// `Eq` on the borrowed form must match those for the key type.
+    /// Dropping the newest entries doesn't move any of the remaining ones,
+    /// so usually only links pointing at the dropped entries are cleared,
+    /// which is a single `O(n)` pass. Should the tree have been rebalanced
+    /// so that some remaining entries hang below dropped ones, it is
+    /// rebuilt instead, which is `O(n)` as well.
     ///
     /// # Examples
     ///
@@ -708,474 +1049,7777 @@ where
     /// use ordnung::Map;
     ///
     /// let mut map = Map::new();
-    /// map.insert(1, "a");
-    /// assert_eq!(map.remove(&1), Some("a"));
-    /// assert_eq!(map.remove(&1), None);
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// map.truncate(1);
+    ///
+    /// assert!(map.keys().eq(&["a"]));
+    /// assert_eq!(map.get("b"), None);
     /// ```
-    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
-    where
-        K: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        let hash = Self::hash_key(key);
+    pub fn truncate(&mut self, len: usize) {
+        let at = self.store_index(len);
 
-        match self.find(hash) {
-            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.take() },
-            Miss(_) => return None,
+        if at >= self.store.len() {
+            return;
         }
-    }
 
-    #[inline]
-    fn find(&self, hash: u64) -> FindResult {
-        if self.len() == 0 {
-            return Miss(None);
+        let mut orphans = false;
+        let mut tombstones = 0;
+
+        for node in self.store.drain(at..) {
+            let below = |link: Option<NonZeroU32>| link.is_some_and(|i| (i.get() as usize) < at);
+
+            orphans |= below(node.left.get()) || below(node.right.get());
+            tombstones += node.value.is_none() as usize;
         }
 
-        let mut idx = 0;
+        self.tombstones -= tombstones;
 
-        loop {
-            let node = unsafe { self.store.get_unchecked(idx) };
+        if orphans {
+            self.rebuild_tree();
+            return;
+        }
 
-            if hash < node.hash {
-                match node.left.get() {
-                    Some(i) => idx = i.get() as usize,
-                    None => return Miss(Some(&node.left)),
-                }
-            } else if hash > node.hash {
-                match node.right.get() {
-                    Some(i) => idx = i.get() as usize,
-                    None => return Miss(Some(&node.right)),
+        for node in self.store.iter() {
+            for link in [&node.left, &node.right].iter() {
+                if link.get().is_some_and(|i| i.get() as usize >= at) {
+                    link.set(None);
                 }
-            } else {
-                return Hit(idx);
             }
         }
     }
 
-    #[inline]
-    fn hash_key<Q: Hash>(key: Q) -> u64 {
-        // let mut hasher = fnv::FnvHasher::default();
-        // let mut hasher = rustc_hash::FxHasher::default();
-        let mut hasher = H::default();
+    /// Removes the `count` oldest entries in insertion order, returning
+    /// them in that order. Evicts all entries if `count` is greater than
+    /// or equal to the map's length.
+    ///
+    /// The tree is rebuilt once over the remaining entries, which makes this
+    /// `O(n)` regardless of `count`, instead of `O(n)` for every entry as
+    /// with [`remove`](#method.remove).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut cache: Map<u32, &str> = Map::new();
+    /// cache.insert(1, "a");
+    /// cache.insert(2, "b");
+    /// cache.insert(3, "c");
+    ///
+    /// let evicted: Vec<_> = cache.evict_oldest(2).collect();
+    ///
+    /// assert_eq!(evicted, [(1, "a"), (2, "b")]);
+    /// assert!(cache.keys().eq(&[3]));
+    /// ```
+    pub fn evict_oldest(&mut self, count: usize) -> impl Iterator<Item = (K, V)> {
+        let at = self.store_index(count);
+        let evicted: alloc::vec::Vec<_> = self.store.drain(..at).collect();
 
-        key.hash(&mut hasher);
+        self.tombstones -= evicted.iter().filter(|node| node.value.is_none()).count();
+        self.rebuild_tree();
 
-        hasher.finish()
+        evicted
+            .into_iter()
+            .filter_map(|node| Some((node.key, node.value?)))
     }
 
-    /// Creates a raw entry builder for the HashMap.
+    /// Shuffles the nodes around with `f`, then rebuilds the tree, since
+    /// the links are invalidated by nodes changing their positions.
+    fn reorder<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut [Node<K, V>]),
+    {
+        f(&mut self.store);
+
+        self.rebuild_tree();
+
+        debug_assert_eq!(self.validate(), Ok(()));
+    }
+
+    /// Swaps the positions of two entries in insertion order.
     ///
-    /// Raw entries provide the lowest level of control for searching and
-    /// manipulating a map. They must be manually initialized with a hash and
-    /// then manually searched. After this, insertions into a vacant entry
-    /// still require an owned key to be provided.
+    /// The tree is rebuilt afterwards, same as with
+    /// [`remove`](#method.remove), which makes this `O(n)`.
     ///
-    /// Raw entries are useful for such exotic situations as:
+    /// # Panics
     ///
-    /// * Hash memoization
-    /// * Deferring the creation of an owned key until it is known to be required
-    /// * Using a search key that doesn't work with the Borrow trait
-    /// * Using custom comparison logic without newtype wrappers
+    /// Panics if `a` or `b` are out of bounds.
     ///
-    /// Because raw entries provide much more low-level control, it's much easier
-    /// to put the HashMap into an inconsistent state which, while memory-safe,
-    /// will cause the map to produce seemingly random results. Higher-level and
-    /// more foolproof APIs like `entry` should be preferred when possible.
+    /// # Examples
     ///
-    /// In particular, the hash used to initialized the raw entry must still be
-    /// consistent with the hash of the key that is ultimately stored in the entry.
-    /// This is because implementations of HashMap may need to recompute hashes
-    /// when resizing, at which point only the keys are available.
+    /// ```rust
+    /// use ordnung::Map;
     ///
-    /// Raw entries give mutable access to the keys. This must not be used
-    /// to modify how the key would compare or hash, as the map will not re-evaluate
-    /// where the key should go, meaning the keys may become "lost" if their
-    /// location does not reflect their state. For instance, if you change a key
-    /// so that the map now contains keys which compare equal, search may start
-    /// acting erratically, with two keys randomly masking each other. Implementations
-    /// are free to assume this doesn't happen (within the limits of memory-safety).
-    #[inline]
-    pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, H> {
-        RawEntryBuilderMut { map: self }
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// map.swap_indices(0, 2);
+    ///
+    /// assert!(map.keys().eq(&["c", "b", "a"]));
+    /// assert_eq!(map["a"], 1);
+    /// ```
+    pub fn swap_indices(&mut self, a: usize, b: usize) {
+        let len = self.len();
+
+        assert!(a < len && b < len, "swap index out of bounds");
+
+        let (a, b) = (self.store_index(a), self.store_index(b));
+
+        self.reorder(|store| store.swap(a, b));
     }
 
-    /// Creates a raw immutable entry builder for the HashMap.
-    ///
-    /// Raw entries provide the lowest level of control for searching and
-    /// manipulating a map. They must be manually initialized with a hash and
-    /// then manually searched.
+    /// Moves the entry at position `from` in insertion order to position
+    /// `to`, shifting all entries in between by one.
     ///
-    /// This is useful for
-    /// * Hash memoization
-    /// * Using a search key that doesn't work with the Borrow trait
-    /// * Using custom comparison logic without newtype wrappers
+    /// The tree is rebuilt afterwards, same as with
+    /// [`remove`](#method.remove), which makes this `O(n)`.
     ///
-    /// Unless you are in such a situation, higher-level and more foolproof APIs like
-    /// `get` should be preferred.
+    /// # Panics
     ///
-    /// Immutable raw entries have very limited use; you might instead want `raw_entry_mut`.
-    #[inline]
-    pub fn raw_entry(&self) -> RawEntryBuilder<'_, K, V, H> {
-        RawEntryBuilder { map: self }
-    }
-
-    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    /// Panics if `from` or `to` are out of bounds.
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```rust
     /// use ordnung::Map;
     ///
-    /// let mut letters = Map::new();
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
     ///
-    /// for ch in "a short treatise on fungi".chars() {
-    ///     let counter = letters.entry(ch).or_insert(0);
-    ///     *counter += 1;
-    /// }
+    /// map.move_index(0, 2);
+    /// assert!(map.keys().eq(&["b", "c", "a"]));
     ///
-    /// assert_eq!(letters[&'s'], 2);
-    /// assert_eq!(letters[&'t'], 3);
-    /// assert_eq!(letters[&'u'], 1);
-    /// assert_eq!(letters.get(&'y'), None);
+    /// map.move_index(2, 1);
+    /// assert!(map.keys().eq(&["b", "a", "c"]));
     /// ```
-    pub fn entry(&mut self, key: K) -> Entry<K, V, H>
-    where
-        K: Eq + Clone,
-    {
-        for (idx, n) in self.store.iter().enumerate() {
-            if &key == &n.key {
-                return Entry::Occupied(OccupiedEntry::new(idx, key, self));
-            }
-        }
-        Entry::Vacant(VacantEntry::new(key, self))
-    }
-}
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        let len = self.len();
 
-impl<K, V> IntoIterator for Map<K, V> {
-    type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
+        assert!(from < len && to < len, "move index out of bounds");
 
-    #[inline]
-    fn into_iter(self) -> IntoIter<K, V> {
-        IntoIter(self.store.into_iter())
+        let (from, to) = (self.store_index(from), self.store_index(to));
+
+        match from.cmp(&to) {
+            Ordering::Less => self.reorder(|store| store[from..=to].rotate_left(1)),
+            Ordering::Greater => self.reorder(|store| store[to..=from].rotate_right(1)),
+            Ordering::Equal => {}
+        }
     }
-}
 
-/// Consuming iterator
-pub struct IntoIter<K, V>(<Vec<Node<K, V>> as IntoIterator>::IntoIter);
+    /// Store index of the live entry at `position` in insertion order, with
+    /// `len()` mapping to the end of the store.
+    fn store_index(&self, position: usize) -> usize {
+        if position >= self.len() {
+            return self.store.len();
+        }
+        if self.tombstones == 0 {
+            return position;
+        }
 
-impl<K, V> ExactSizeIterator for IntoIter<K, V> {
-    fn len(&self) -> usize {
-        self.0.len()
+        self.store
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.value.is_some())
+            .nth(position)
+            .map_or(self.store.len(), |(idx, _)| idx)
     }
-}
 
-impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
-    #[inline]
-    fn next_back(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Some(n) = self.0.next_back() {
-                if let Some(v) = n.value {
-                    return Some((n.key, v));
-                }
-            } else {
-                return None;
-            }
+    /// Position in insertion order of the entry at store index `idx`.
+    pub(crate) fn position_of(&self, idx: usize) -> usize {
+        if self.tombstones == 0 {
+            return idx;
         }
+
+        idx - self.store[..idx]
+            .iter()
+            .filter(|node| node.value.is_none())
+            .count()
     }
-}
 
-impl<K, V> Iterator for IntoIter<K, V> {
-    type Item = (K, V);
+    /// Returns the index of the root node of the tree, or `None` if the
+    /// tree has no nodes, counting entries removed with
+    /// [`remove_lazy`](#method.remove_lazy).
+    ///
+    /// Lazily removed entries stay in the tree as tombstones until the map
+    /// is [`compact`](#method.compact)ed, so a map can be empty and still
+    /// have a root, which is then a tombstone itself. As long as the root
+    /// is a live entry, the index is also its position in insertion order.
+    ///
+    /// The root is currently always the first node, but tooling inspecting
+    /// the tree should not rely on that.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.root_index(), None);
+    ///
+    /// map.insert("a", 1);
+    /// assert_eq!(map.root_index(), Some(0));
+    ///
+    /// map.remove_lazy("a");
+    /// assert!(map.is_empty());
+    /// assert_eq!(map.root_index(), Some(0));
+    ///
+    /// map.compact();
+    /// assert_eq!(map.root_index(), None);
+    /// ```
     #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Some(n) = self.0.next() {
-                if let Some(v) = n.value {
-                    return Some((n.key, v));
+    pub fn root_index(&self) -> Option<usize> {
+        if self.store.is_empty() {
+            None
+        } else {
+            Some(0)
+        }
+    }
+
+    /// Returns the entry at the given position in insertion order, or `None`
+    /// if `index` is out of bounds.
+    ///
+    /// This is `O(1)`, unless entries were removed with
+    /// [`remove_lazy`](#method.remove_lazy) and not yet compacted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.get_index(1), Some((&"b", &2)));
+    /// assert_eq!(map.get_index(2), None);
+    /// ```
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let node = &self.store[self.store_index(index)];
+
+        node.value.as_ref().map(|value| (&node.key, value))
+    }
+
+    /// Returns the hash stored for the entry at the given position in
+    /// insertion order, or `None` if `index` is out of bounds. Unlike
+    /// [`get_hash`](#method.get_hash), this doesn't hash anything.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// assert!(map.hash_at(0).is_some());
+    /// assert_eq!(map.hash_at(1), None);
+    /// ```
+    pub fn hash_at(&self, index: usize) -> Option<u64> {
+        if index >= self.len() {
+            return None;
+        }
+
+        Some(self.store[self.store_index(index)].hash)
+    }
+
+    /// Returns the entry at the given position in insertion order, with a
+    /// mutable reference to the value, or `None` if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// if let Some((_, value)) = map.get_index_mut(0) {
+    ///     *value = 10;
+    /// }
+    ///
+    /// assert_eq!(map["a"], 10);
+    /// assert_eq!(map.get_index_mut(1), None);
+    /// ```
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let idx = self.store_index(index);
+        let Node { key, value, .. } = &mut self.store[idx];
+
+        value.as_mut().map(move |value| (&*key, value))
+    }
+
+    /// Returns the entry at the given position in insertion order, without
+    /// doing bounds checking.
+    ///
+    /// This is `O(1)`, unless entries were removed with
+    /// [`remove_lazy`](#method.remove_lazy) and not yet compacted, same as
+    /// [`get_index`](#method.get_index).
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an `index` not less than
+    /// [`len`](#method.len) is undefined behavior.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// if let Some((index, _, _)) = map.get_full("b") {
+    ///     // `get_full` only returns positions of entries in the map
+    ///     let entry = unsafe { map.get_index_unchecked(index) };
+    ///
+    ///     assert_eq!(entry, (&"b", &2));
+    /// }
+    /// ```
+    #[inline]
+    pub unsafe fn get_index_unchecked(&self, index: usize) -> (&K, &V) {
+        debug_assert!(index < self.len(), "map index out of bounds");
+
+        let node = self.store.get_unchecked(self.store_index(index));
+
+        (&node.key, node.value.as_ref().unwrap_unchecked())
+    }
+
+    /// Returns the entry at the given position in insertion order, with a
+    /// mutable reference to the value, without doing bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// Calling this method with an `index` not less than
+    /// [`len`](#method.len) is undefined behavior.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// if let Some((index, _, _)) = map.get_full("a") {
+    ///     let (_, value) = unsafe { map.get_index_unchecked_mut(index) };
+    ///
+    ///     *value = 10;
+    /// }
+    ///
+    /// assert_eq!(map["a"], 10);
+    /// ```
+    #[inline]
+    pub unsafe fn get_index_unchecked_mut(&mut self, index: usize) -> (&K, &mut V) {
+        debug_assert!(index < self.len(), "map index out of bounds");
+
+        let idx = self.store_index(index);
+        let Node { key, value, .. } = self.store.get_unchecked_mut(idx);
+
+        (&*key, value.as_mut().unwrap_unchecked())
+    }
+
+    /// Returns the first entry in insertion order, or `None` if the map is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.first(), None);
+    ///
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// assert_eq!(map.first(), Some((&"a", &1)));
+    /// ```
+    #[inline]
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    /// Returns the first entry in insertion order, with a mutable reference
+    /// to the value, or `None` if the map is empty.
+    #[inline]
+    pub fn first_mut(&mut self) -> Option<(&K, &mut V)> {
+        self.iter_mut().next()
+    }
+
+    /// Returns the most recently inserted entry, or `None` if the map is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.last(), None);
+    ///
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// assert_eq!(map.last(), Some((&"b", &2)));
+    /// ```
+    #[inline]
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    /// Returns the most recently inserted entry, with a mutable reference to
+    /// the value, or `None` if the map is empty.
+    #[inline]
+    pub fn last_mut(&mut self) -> Option<(&K, &mut V)> {
+        self.iter_mut().next_back()
+    }
+
+    /// Returns an occupied entry for the first entry in insertion order,
+    /// or `None` if the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// if let Some(mut entry) = map.first_entry() {
+    ///     *entry.get_mut() += 10;
+    /// }
+    /// assert_eq!(map["a"], 11);
+    ///
+    /// assert_eq!(map.first_entry().unwrap().remove(), 11);
+    /// assert!(map.keys().eq(&["b"]));
+    /// ```
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, S>>
+    where
+        K: Clone,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        let idx = self.store_index(0);
+        let key = self.store[idx].key.clone();
+
+        Some(OccupiedEntry::new(idx, key, self))
+    }
+
+    /// Returns an occupied entry for the most recently inserted entry, or
+    /// `None` if the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let entry = map.last_entry().unwrap();
+    /// assert_eq!(entry.key(), &"b");
+    /// assert_eq!(entry.remove(), 2);
+    ///
+    /// assert!(map.keys().eq(&["a"]));
+    /// ```
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V, S>>
+    where
+        K: Clone,
+    {
+        let position = self.len().checked_sub(1)?;
+        let idx = self.store_index(position);
+        let key = self.store[idx].key.clone();
+
+        Some(OccupiedEntry::new(idx, key, self))
+    }
+
+    /// Returns the number of elements the map can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.store.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be
+    /// inserted in the map. Does nothing if the capacity is already
+    /// sufficient.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows a `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<&str, u32> = Map::new();
+    /// map.reserve(10);
+    ///
+    /// assert!(map.capacity() >= 10);
+    /// ```
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.store.reserve(additional);
+    }
+
+    /// Reserves the minimum capacity for exactly `additional` more elements
+    /// to be inserted in the map.
+    ///
+    /// Prefer [`reserve`](#method.reserve) if future insertions are expected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows a `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.reserve_exact(10);
+    ///
+    /// assert_eq!(map.capacity(), 11);
+    /// ```
+    #[inline]
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.store.reserve_exact(additional);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to
+    /// be inserted in the map, same as [`reserve`](#method.reserve), but
+    /// returns an error instead of panicking or aborting when the memory
+    /// can't be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::{Map, TryReserveError};
+    ///
+    /// let mut map: Map<&str, i32> = Map::new();
+    ///
+    /// assert_eq!(map.try_reserve(10), Ok(()));
+    /// assert!(map.capacity() >= 10);
+    /// assert_eq!(map.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    /// ```
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        #[cfg(not(feature = "alloc-vec"))]
+        return self.store.try_reserve(additional);
+
+        // Same limit as `compact::Vec`, more entries can't be linked anyway
+        #[cfg(feature = "alloc-vec")]
+        match self.store.len().checked_add(additional) {
+            Some(required) if required <= u32::MAX as usize => self
+                .store
+                .try_reserve(additional)
+                .map_err(|_| TryReserveError::AllocError),
+            _ => Err(TryReserveError::CapacityOverflow),
+        }
+    }
+
+    /// Shrinks the capacity of the map as much as possible.
+    ///
+    /// Entries keep their positions, so the tree stays intact without
+    /// being rebuilt.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::with_capacity(100);
+    /// map.insert(1, 2);
+    /// map.insert(3, 4);
+    ///
+    /// map.shrink_to_fit();
+    ///
+    /// assert_eq!(map.capacity(), 2);
+    /// assert_eq!(map.get(&3), Some(&4));
+    /// ```
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.store.shrink_to_fit();
+    }
+
+    /// Shrinks the capacity of the map with a lower bound.
+    ///
+    /// The capacity will remain at least as large as both the storage taken
+    /// up by the entries and `min_capacity`. Does nothing if the capacity is
+    /// already smaller. As with [`shrink_to_fit`](#method.shrink_to_fit),
+    /// the tree stays intact.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::with_capacity(100);
+    /// map.insert(1, 2);
+    /// map.insert(3, 4);
+    ///
+    /// map.shrink_to(10);
+    /// assert_eq!(map.capacity(), 10);
+    ///
+    /// map.shrink_to(0);
+    /// assert_eq!(map.capacity(), 2);
+    /// assert_eq!(map.get(&3), Some(&4));
+    /// ```
+    #[inline]
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.store.shrink_to(min_capacity);
+    }
+
+    /// Clears the map, removing all key-value pairs. Keeps the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    /// let capacity = map.capacity();
+    ///
+    /// map.clear();
+    ///
+    /// assert!(map.is_empty());
+    /// assert_eq!(map.capacity(), capacity);
+    /// ```
+    #[inline]
+    pub fn clear(&mut self) {
+        self.store.clear();
+        self.tombstones = 0;
+    }
+
+    /// Clears the map, removing all key-value pairs, and frees the allocated
+    /// memory. Keeps the hasher.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// map.clear_and_shrink();
+    ///
+    /// assert!(map.is_empty());
+    /// assert_eq!(map.capacity(), 0);
+    /// ```
+    #[inline]
+    pub fn clear_and_shrink(&mut self) {
+        self.store = Store::new();
+        self.tombstones = 0;
+    }
+
+    /// Removes the most recently inserted entry and returns it, or `None` if
+    /// the map is empty.
+    ///
+    /// The tree doesn't need to be rebuilt, but finding the link pointing to
+    /// the entry is a scan over all entries, which makes this `O(n)`, though
+    /// still much cheaper than [`remove`](#method.remove).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.pop(), Some(("b", 2)));
+    /// assert_eq!(map.pop(), Some(("a", 1)));
+    /// assert_eq!(map.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        while let Some(node) = self.store.pop() {
+            if let Some(link) = NonZeroU32::new(self.store.len() as u32) {
+                for parent in self.store.iter() {
+                    if parent.left.get() == Some(link) {
+                        parent.left.set(None);
+                        break;
+                    }
+                    if parent.right.get() == Some(link) {
+                        parent.right.set(None);
+                        break;
+                    }
+                }
+            }
+
+            // Only a rebalanced tree can have older entries below newer ones
+            if node.left.get().is_some() || node.right.get().is_some() {
+                self.rebuild_tree();
+            }
+
+            match node.value {
+                Some(value) => return Some((node.key, value)),
+                None => self.tombstones -= 1,
+            }
+        }
+
+        None
+    }
+
+    /// Clears the map, returning all key-value pairs as an iterator in
+    /// insertion order. Keeps the allocated memory for reuse.
+    ///
+    /// Entries not consumed by the iterator are dropped when it is dropped.
+    /// Should the iterator be leaked, the remaining entries are leaked too,
+    /// but the map is still left empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let entries: Vec<_> = map.drain().collect();
+    ///
+    /// assert_eq!(entries, &[("a", 1), ("b", 2)]);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let len = self.len();
+
+        self.tombstones = 0;
+
+        Drain {
+            inner: self.store.drain(..),
+            len,
+        }
+    }
+
+    /// Takes all entries out of the map, returning them as a new `Map`.
+    ///
+    /// The map is left empty, with a fresh allocation of the same capacity,
+    /// so it can be filled again without growing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::with_capacity(16);
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let previous = map.take_all();
+    ///
+    /// assert!(map.is_empty());
+    /// assert_eq!(map.capacity(), 16);
+    /// assert_eq!(previous.len(), 2);
+    /// assert_eq!(previous["a"], 1);
+    /// ```
+    pub fn take_all(&mut self) -> Map<K, V, S>
+    where
+        S: Clone,
+    {
+        let store = Store::with_capacity(self.store.capacity());
+
+        Map {
+            store: core::mem::replace(&mut self.store, store),
+            tombstones: core::mem::take(&mut self.tombstones),
+            auto_rebalance: self.auto_rebalance,
+            balance_on_extend: false,
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in insertion order.
+    /// The iterator element type is `(&K, &V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// let entries: Vec<_> = map.iter().collect();
+    ///
+    /// assert_eq!(
+    ///     entries,
+    ///     &[
+    ///         (&"a", &1),
+    ///         (&"b", &2),
+    ///         (&"c", &3),
+    ///     ],
+    /// );
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.store.iter(),
+            len: self.len(),
+        }
+    }
+
+    /// Borrows the entries of the map as `(&K, &V)` pairs in insertion
+    /// order, without allocating.
+    ///
+    /// Entries aren't stored as `(K, V)` tuples, so there is no way to hand
+    /// out a `&[(K, V)]`. This is the same as [`iter`](#method.iter), and
+    /// exists for discoverability.
+    #[inline]
+    pub fn as_pairs(&self) -> Iter<'_, K, V> {
+        self.iter()
+    }
+
+    /// Returns `true` if both maps contain the same entries in the same
+    /// insertion order.
+    ///
+    /// Comparing maps with `==` ignores the order of entries, as it does
+    /// for any other map. Use this instead when the order is meaningful,
+    /// such as when checking the output a map serializes to.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let a: Map<&str, i32> = Map::from([("a", 1), ("b", 2)]);
+    /// let b: Map<&str, i32> = Map::from([("b", 2), ("a", 1)]);
+    ///
+    /// assert_eq!(a, b);
+    /// assert!(!a.eq_ordered(&b));
+    /// assert!(a.eq_ordered(&a.clone()));
+    /// ```
+    pub fn eq_ordered(&self, other: &Self) -> bool
+    where
+        K: PartialEq,
+        V: PartialEq,
+    {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+
+    /// Clones all entries of the map into a `Vec` of `(K, V)` pairs, in
+    /// insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.to_vec(), vec![("a", 1), ("b", 2)]);
+    /// ```
+    pub fn to_vec(&self) -> alloc::vec::Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// An iterator visiting all key-value pairs in insertion order, with
+    /// mutable references to the values. The iterator element type is
+    /// (&K, &mut V).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    ///
+    /// // Update all values
+    /// for (_, val) in map.iter_mut() {
+    ///     *val *= 2;
+    /// }
+    ///
+    /// // Check if values are doubled
+    /// let entries: Vec<_> = map.iter().collect();
+    ///
+    /// assert_eq!(
+    ///     entries,
+    ///     &[
+    ///         (&"a", &2),
+    ///         (&"b", &4),
+    ///         (&"c", &6),
+    ///     ],
+    /// );
+    /// ```
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            len: self.len(),
+            inner: self.store.iter_mut(),
+        }
+    }
+
+    /// An iterator visiting all entries in insertion order, along with the
+    /// position of each, as accepted by [`get_index`](#method.get_index)
+    /// and returned by [`get_full`](#method.get_full).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    /// map.remove_lazy("a");
+    ///
+    /// for (index, key, value) in map.iter_indexed() {
+    ///     assert_eq!(map.get_index(index), Some((key, value)));
+    /// }
+    ///
+    /// assert!(map.iter_indexed().eq(vec![(0, &"b", &2), (1, &"c", &3)]));
+    /// ```
+    pub fn iter_indexed(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (usize, &K, &V)> + ExactSizeIterator + FusedIterator {
+        self.iter()
+            .enumerate()
+            .map(|(index, (key, value))| (index, key, value))
+    }
+
+    /// An iterator visiting all entries in insertion order, with mutable
+    /// references to the values, along with the position of each, same as
+    /// [`iter_indexed`](#method.iter_indexed).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// for (index, _, value) in map.iter_indexed_mut() {
+    ///     *value += index * 10;
+    /// }
+    ///
+    /// assert_eq!(map["a"], 1);
+    /// assert_eq!(map["b"], 12);
+    /// ```
+    pub fn iter_indexed_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (usize, &K, &mut V)> + ExactSizeIterator + FusedIterator
+    {
+        self.iter_mut()
+            .enumerate()
+            .map(|(index, (key, value))| (index, key, value))
+    }
+
+    /// An iterator visiting all keys in insertion order.
+    /// The iterator element type is `&K`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let keys: Vec<_> = map.keys().collect();
+    ///
+    /// assert_eq!(keys, &[&"a", &"b"]);
+    /// ```
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values in insertion order.
+    /// The iterator element type is `&V`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let values: Vec<_> = map.values().collect();
+    ///
+    /// assert_eq!(values, &[&1, &2]);
+    /// ```
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// An iterator visiting all values mutably in insertion order.
+    /// The iterator element type is `&mut V`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// for value in map.values_mut() {
+    ///     *value += 10;
+    /// }
+    ///
+    /// let values: Vec<_> = map.values().collect();
+    ///
+    /// assert_eq!(values, &[&11, &12]);
+    /// ```
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Consumes the map, returning an iterator over its keys in insertion order.
+    /// The iterator element type is `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("a"), 1);
+    /// map.insert(String::from("b"), 2);
+    ///
+    /// let keys: Vec<String> = map.into_keys().collect();
+    ///
+    /// assert_eq!(keys, &["a", "b"]);
+    /// ```
+    #[inline]
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys(self.into_iter())
+    }
+
+    /// Consumes the map, returning an iterator over its values in insertion order.
+    /// The iterator element type is `V`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", String::from("x"));
+    /// map.insert("b", String::from("y"));
+    ///
+    /// let values: Vec<String> = map.into_values().rev().collect();
+    ///
+    /// assert_eq!(values, &["y", "x"]);
+    /// ```
+    #[inline]
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues(self.into_iter())
+    }
+
+    /// Consumes the map, returning its entries as a standard `Vec` of
+    /// key-value pairs in insertion order.
+    ///
+    /// The entries are stored in insertion order to begin with, this only
+    /// strips them of the tree links, and drops any entries removed with
+    /// [`remove_lazy`](#method.remove_lazy).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// assert_eq!(map.into_entries(), vec![("b", 2), ("a", 1)]);
+    /// ```
+    pub fn into_entries(self) -> alloc::vec::Vec<(K, V)> {
+        self.into_iter().collect()
+    }
+
+    /// Clones all values into a standard `Vec`, in insertion order.
+    ///
+    /// Values are stored interleaved with their keys and tree links, so
+    /// there is no `&[V]` to borrow. For numeric work over many values it
+    /// can pay off to copy them into a contiguous buffer once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<&str, f64> = vec![("a", 1.5), ("b", 2.5)].into_iter().collect();
+    ///
+    /// let values = map.values_contiguous();
+    ///
+    /// assert_eq!(values, [1.5, 2.5]);
+    /// assert_eq!(values.iter().sum::<f64>(), 4.0);
+    /// ```
+    pub fn values_contiguous(&self) -> alloc::vec::Vec<V>
+    where
+        V: Clone,
+    {
+        self.values().cloned().collect()
+    }
+
+    /// An iterator visiting key-value pairs in insertion order, with mutable
+    /// references to the values, skipping all entries for which `pred`
+    /// returns `false` for the key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("xa", 1);
+    /// map.insert("b", 2);
+    /// map.insert("xc", 3);
+    ///
+    /// for (_, val) in map.values_mut_where(|k| k.starts_with('x')) {
+    ///     *val *= 10;
+    /// }
+    ///
+    /// let entries: Vec<_> = map.iter().collect();
+    ///
+    /// assert_eq!(
+    ///     entries,
+    ///     &[
+    ///         (&"xa", &10),
+    ///         (&"b", &2),
+    ///         (&"xc", &30),
+    ///     ],
+    /// );
+    /// ```
+    #[inline]
+    pub fn values_mut_where<P>(&mut self, mut pred: P) -> impl Iterator<Item = (&K, &mut V)>
+    where
+        P: FnMut(&K) -> bool,
+    {
+        self.iter_mut().filter(move |(k, _)| pred(k))
+    }
+
+    /// An iterator visiting values at positions within `range`, in insertion
+    /// order. The range is clamped to the length of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<char, u32> = ('a'..='f').zip(0..6u32).collect();
+    ///
+    /// let page: Vec<_> = map.values_in_range(2..4).collect();
+    /// assert_eq!(page, &[&2, &3]);
+    ///
+    /// let tail: Vec<_> = map.values_in_range(4..100).collect();
+    /// assert_eq!(tail, &[&4, &5]);
+    /// ```
+    pub fn values_in_range<R>(&self, range: R) -> impl Iterator<Item = &V>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = clamp_range(range, self.len());
+        let (start, end) = (self.store_index(start), self.store_index(end));
+
+        self.store[start..end]
+            .iter()
+            .filter_map(|node| node.value.as_ref())
+    }
+
+    /// An iterator visiting the entries at positions within `range`, in
+    /// insertion order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds of the map, or its start is past
+    /// its end, same as slice indexing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<char, u32> = ('a'..='f').zip(0..6u32).collect();
+    ///
+    /// assert!(map.range(2..4).eq(vec![(&'c', &2), (&'d', &3)]));
+    /// assert!(map.range(..=1).eq(vec![(&'a', &0), (&'b', &1)]));
+    /// assert_eq!(map.range(4..).len(), 2);
+    /// ```
+    pub fn range<R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = check_range(range, self.len());
+
+        Iter {
+            inner: self.store[self.store_index(start)..self.store_index(end)].iter(),
+            len: end - start,
+        }
+    }
+
+    /// An iterator visiting the entries at positions within `range`, in
+    /// insertion order, with mutable references to the values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds of the map, or its start is past
+    /// its end, same as slice indexing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<char, u32> = ('a'..='f').zip(0..6u32).collect();
+    ///
+    /// for (_, value) in map.range_mut(1..3) {
+    ///     *value *= 10;
+    /// }
+    ///
+    /// assert!(map.values().eq(&[0, 10, 20, 3, 4, 5]));
+    /// ```
+    pub fn range_mut<R>(&mut self, range: R) -> IterMut<'_, K, V>
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = check_range(range, self.len());
+        let (from, to) = (self.store_index(start), self.store_index(end));
+
+        IterMut {
+            len: end - start,
+            inner: self.store[from..to].iter_mut(),
+        }
+    }
+
+    /// Calls `f` on every entry in insertion order, stopping at the first
+    /// error and returning it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", -2);
+    /// map.insert("c", 3);
+    ///
+    /// let mut sum = 0;
+    /// let result = map.try_for_each(|key, &value| {
+    ///     if value < 0 {
+    ///         return Err(*key);
+    ///     }
+    ///     sum += value;
+    ///     Ok(())
+    /// });
+    ///
+    /// assert_eq!(result, Err("b"));
+    /// assert_eq!(sum, 1);
+    /// ```
+    pub fn try_for_each<E, F>(&self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&K, &V) -> Result<(), E>,
+    {
+        self.iter().try_for_each(|(key, value)| f(key, value))
+    }
+
+    /// Calls `f` on every entry in insertion order, returning the first
+    /// non-`None` result.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", "1");
+    /// map.insert("b", "two");
+    /// map.insert("c", "3");
+    ///
+    /// let first = map.find_map(|_, value| value.parse::<u32>().ok());
+    /// let invalid = map.find_map(|key, value| value.parse::<u32>().err().map(|_| *key));
+    ///
+    /// assert_eq!(first, Some(1));
+    /// assert_eq!(invalid, Some("b"));
+    /// ```
+    pub fn find_map<B, F>(&self, mut f: F) -> Option<B>
+    where
+        F: FnMut(&K, &V) -> Option<B>,
+    {
+        self.iter().find_map(|(key, value)| f(key, value))
+    }
+
+    /// Returns the number of entries for which `f` returns `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<u32, u32> = (0..10u32).map(|x| (x, x * x)).collect();
+    ///
+    /// assert_eq!(map.count_matching(|_, &square| square > 20), 5);
+    /// ```
+    pub fn count_matching<F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        self.iter().filter(|&(key, value)| f(key, value)).count()
+    }
+
+    /// Folds every entry into an accumulator in insertion order, returning
+    /// the final result.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("bb", 2);
+    /// map.insert("ccc", 3);
+    ///
+    /// let weighted = map.fold(0, |acc, key, value| acc + key.len() * value);
+    ///
+    /// assert_eq!(weighted, 14);
+    /// ```
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &K, &V) -> B,
+    {
+        self.iter()
+            .fold(init, |acc, (key, value)| f(acc, key, value))
+    }
+
+    /// Returns the entry with the largest value, or `None` if the map is empty.
+    /// Of equal values, the one inserted first is returned.
+    ///
+    /// This scans all entries and is `O(n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 3);
+    /// map.insert("c", 3);
+    ///
+    /// assert_eq!(map.max_value(), Some((&"b", &3)));
+    /// ```
+    pub fn max_value(&self) -> Option<(&K, &V)>
+    where
+        V: Ord,
+    {
+        self.iter().fold(None, |max, entry| match max {
+            Some((_, value)) if value >= entry.1 => max,
+            _ => Some(entry),
+        })
+    }
+
+    /// Returns the entry with the smallest value, or `None` if the map is empty.
+    /// Of equal values, the one inserted first is returned.
+    ///
+    /// This scans all entries and is `O(n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 3);
+    /// map.insert("b", 1);
+    /// map.insert("c", 1);
+    ///
+    /// assert_eq!(map.min_value(), Some((&"b", &1)));
+    /// ```
+    pub fn min_value(&self) -> Option<(&K, &V)>
+    where
+        V: Ord,
+    {
+        self.iter().fold(None, |min, entry| match min {
+            Some((_, value)) if value <= entry.1 => min,
+            _ => Some(entry),
+        })
+    }
+
+    /// Clones the map into an allocation holding exactly its entries.
+    ///
+    /// Unlike [`freeze`](#method.freeze) the tree is left as it is, this
+    /// only trims the spare capacity, which is useful for long-lived copies.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::with_capacity(64);
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let replica = map.clone_compact();
+    ///
+    /// assert_eq!(replica.capacity(), 2);
+    /// assert_eq!(replica, map);
+    /// ```
+    pub fn clone_compact(&self) -> Self
+    where
+        K: Clone,
+        V: Clone,
+        S: Clone,
+    {
+        let mut store = Store::with_capacity(self.len());
+
+        for node in self.store.iter() {
+            if node.value.is_some() {
+                store.push(node.clone());
+            }
+        }
+
+        let mut map = Map {
+            store,
+            tombstones: 0,
+            auto_rebalance: self.auto_rebalance,
+            balance_on_extend: false,
+            hasher: self.hasher.clone(),
+        };
+
+        // Links of the clones point past dropped tombstones
+        if self.tombstones > 0 {
+            map.rebuild_tree();
+        }
+
+        map
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all pairs `(k, v)` such that `f(&k, &mut v)`
+    /// returns `false`. The elements are visited in insertion order, and the
+    /// order of the retained elements is preserved.
+    ///
+    /// If the predicate keeps every element the map is left untouched,
+    /// otherwise the storage is compacted and the tree is rebuilt once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..8u32).map(|x| (x, x * 10)).collect();
+    /// map.retain(|&k, _| k % 2 == 0);
+    ///
+    /// let entries: Vec<_> = map.iter().collect();
+    ///
+    /// assert_eq!(entries, &[(&0, &0), (&2, &20), (&4, &40), (&6, &60)]);
+    /// assert_eq!(map.get(&4), Some(&40));
+    /// assert_eq!(map.get(&5), None);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        // First pass only looks for the first entry to drop, so that
+        // a predicate keeping everything never touches the store.
+        let first = self.store.iter_mut().position(|node| match node.value {
+            Some(ref mut value) => !f(&node.key, value),
+            None => false,
+        });
+
+        let first = match first {
+            Some(first) => first,
+            None => return,
+        };
+
+        let old = core::mem::take(&mut self.store);
+        let mut store = Store::with_capacity(old.capacity());
+
+        self.tombstones = 0;
+
+        for (idx, mut node) in old.into_iter().enumerate() {
+            let keep = match node.value {
+                Some(ref mut value) => idx < first || (idx > first && f(&node.key, value)),
+                None => false,
+            };
+
+            if keep {
+                store.push(node);
+            }
+        }
+
+        // Only put the store back once it's complete, a panic in `f` leaves
+        // the map empty rather than with dangling links.
+        self.store = store;
+        self.rebuild_tree();
+    }
+
+    /// Same as [`retain`](#method.retain), but also passes the position of
+    /// each entry in insertion order to the predicate.
+    ///
+    /// Positions are those from before any entry is removed, so they don't
+    /// match the positions of the retained entries once this returns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..8u32).map(|x| (x, x * 10)).collect();
+    ///
+    /// map.remove(&2);
+    /// map.retain_indexed(|index, _, _| index % 2 == 0);
+    ///
+    /// assert!(map.keys().eq(&[0, 3, 5, 7]));
+    /// ```
+    pub fn retain_indexed<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, &K, &mut V) -> bool,
+    {
+        // `retain` calls the predicate exactly once per entry, in order
+        let mut index = 0;
+
+        self.retain(|key, value| {
+            index += 1;
+            f(index - 1, key, value)
+        });
+    }
+
+    /// Retains only the elements for which the fallible predicate returns
+    /// `Ok(true)`.
+    ///
+    /// The predicate is called for every element in insertion order before
+    /// anything is removed. Should it return an error, iteration stops and
+    /// the error is returned with the map left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<&str, i32> = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", -2);
+    /// map.insert("c", 3);
+    ///
+    /// let result = map.try_retain(|_, &v| if v < 0 { Err(v) } else { Ok(v > 1) });
+    ///
+    /// assert_eq!(result, Err(-2));
+    /// assert_eq!(map.len(), 3);
+    ///
+    /// map.insert("b", 2);
+    ///
+    /// let result: Result<(), i32> = map.try_retain(|_, &v| Ok(v > 1));
+    ///
+    /// assert_eq!(result, Ok(()));
+    /// assert_eq!(map.len(), 2);
+    /// assert_eq!(map.get("a"), None);
+    /// ```
+    pub fn try_retain<F, E>(&mut self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(&K, &V) -> Result<bool, E>,
+    {
+        let mut keep = alloc::vec::Vec::with_capacity(self.store.len());
+
+        for (key, value) in self.iter() {
+            keep.push(f(key, value)?);
+        }
+
+        // `retain` visits entries in the same order, exactly once each
+        let mut keep = keep.into_iter();
+
+        self.retain(|_, _| keep.next().unwrap_or(false));
+
+        Ok(())
+    }
+
+    /// Remove the node at `idx`, shifting all following nodes to the left.
+    ///
+    /// Since shifting invalidates the links of every node past `idx`, the
+    /// whole tree has to be rebuilt, which makes this `O(n)`. The last node
+    /// has no nodes following it, so it's only unlinked from the tree.
+    pub(crate) fn remove_index(&mut self, idx: usize) -> (K, V) {
+        let node = match self.removal_kind(idx) {
+            RemovalKind::Cheap => {
+                self.unlink(idx);
+                self.store.pop().expect("Removed node must exist")
+            }
+            RemovalKind::Rebuild => {
+                let node = self.store.remove(idx);
+
+                self.rebuild_tree();
+                node
+            }
+        };
+
+        (node.key, node.value.expect("Removed node must be occupied"))
+    }
+
+    #[inline]
+    fn removal_kind(&self, idx: usize) -> RemovalKind {
+        if idx + 1 == self.store.len() {
+            RemovalKind::Cheap
+        } else {
+            RemovalKind::Rebuild
+        }
+    }
+
+    /// Remove the node at `idx` by moving the last node into its place.
+    ///
+    /// Only the links to the removed node and the moved one change, so the
+    /// rest of the tree is left untouched.
+    fn swap_remove_index(&mut self, idx: usize) -> (K, V) {
+        let replacement = self.unlink(idx);
+
+        // The root has to stay first, so the node replacing it in the tree
+        // takes its place in the store, leaving the hole where it was.
+        let hole = match replacement {
+            Some(link) if idx == 0 => {
+                let hole = link.get() as usize;
+
+                self.store.swap(0, hole);
+                hole
+            }
+            _ => idx,
+        };
+
+        let last = self.store.len() - 1;
+
+        if hole != last {
+            if let Some(link) = self.parent_link(last) {
+                link.set(link_to(hole));
+            }
+        }
+
+        let node = self.store.swap_remove(hole);
+
+        (node.key, node.value.expect("Removed node must be occupied"))
+    }
+
+    /// Returns the link pointing to the node at `idx`, or `None` for the
+    /// root, found by walking the tree down along its hash.
+    fn parent_link(&self, idx: usize) -> Option<&Cell<Option<NonZeroU32>>> {
+        let hash = self.store[idx].hash;
+        let mut current = 0;
+
+        while current != idx {
+            let node = &self.store[current];
+
+            let link = if hash < node.hash {
+                &node.left
+            } else {
+                &node.right
+            };
+
+            let next = link.get().expect("node is not linked into the tree").get() as usize;
+
+            if next == idx {
+                return Some(link);
+            }
+
+            current = next;
+        }
+
+        None
+    }
+
+    /// Takes the node at `idx` out of the tree, linking the node that takes
+    /// its place into its parent, and returns the link to that node.
+    fn unlink(&self, idx: usize) -> Option<NonZeroU32> {
+        let node = &self.store[idx];
+
+        let replacement = match (node.left.get(), node.right.get()) {
+            (None, only) | (only, None) => only,
+            (Some(left), Some(right)) => {
+                // The leftmost node of the right subtree has the smallest
+                // hash not below ours, so it can take our place.
+                let mut link = &node.right;
+                let mut successor = &self.store[right.get() as usize];
+
+                while let Some(next) = successor.left.get() {
+                    link = &successor.left;
+                    successor = &self.store[next.get() as usize];
+                }
+
+                let successor_link = link.get();
+
+                link.set(successor.right.get());
+                successor.left.set(Some(left));
+                successor.right.set(node.right.get());
+
+                successor_link
+            }
+        };
+
+        if let Some(parent) = self.parent_link(idx) {
+            parent.set(replacement);
+        }
+
+        node.left.set(None);
+        node.right.set(None);
+
+        replacement
+    }
+
+    /// Pushes a node whose parent is already linked to it, returning its
+    /// store index. Every new entry goes through here, so that a map with
+    /// [automatic rebalancing](#method.set_auto_rebalance) is checked each
+    /// time its length reaches a power of two, however it was filled.
+    #[inline]
+    fn push_node(&mut self, node: Node<K, V>) -> usize {
+        let idx = self.store.len();
+
+        self.store.push(node);
+
+        let len = self.store.len();
+
+        // Balancing only relinks the tree, `idx` stays valid
+        if self.auto_rebalance && len.is_power_of_two() && !self.is_reasonably_balanced() {
+            self.balance_tree();
+        }
+
+        idx
+    }
+
+    /// Recompute `left` and `right` links of all nodes from their hashes,
+    /// as if they were inserted in current store order.
+    fn rebuild_tree(&mut self) {
+        for node in self.store.iter() {
+            node.left.set(None);
+            node.right.set(None);
+        }
+
+        for (idx, node) in self.store.iter().enumerate().skip(1) {
+            let mut parent = 0;
+
+            loop {
+                let current = unsafe { self.store.get_unchecked(parent) };
+
+                let link = if node.hash < current.hash {
+                    &current.left
+                } else {
+                    &current.right
+                };
+
+                match link.get() {
+                    Some(i) => parent = i.get() as usize,
+                    None => {
+                        link.set(NonZeroU32::new(idx as u32));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Recompute `left` and `right` links of all nodes so that the tree is
+    /// as balanced as it can be with the first node as the root.
+    fn balance_tree(&mut self) {
+        for node in self.store.iter() {
+            node.left.set(None);
+            node.right.set(None);
+        }
+
+        if self.store.len() < 2 {
+            return;
+        }
+
+        let store = &self.store;
+        let root = &store[0];
+
+        // Stable sort, so nodes with colliding hashes keep insertion order
+        let mut sorted: alloc::vec::Vec<u32> = (1..store.len() as u32).collect();
+        sorted.sort_by_key(|&idx| store[idx as usize].hash);
+
+        let split = sorted.partition_point(|&idx| store[idx as usize].hash < root.hash);
+        let (left, right) = sorted.split_at(split);
+
+        root.left.set(Self::link_balanced(store, left));
+        root.right.set(Self::link_balanced(store, right));
+    }
+
+    /// Link nodes at `sorted` indices median-first, returning the index
+    /// of the subtree root.
+    fn link_balanced(store: &[Node<K, V>], sorted: &[u32]) -> Option<NonZeroU32> {
+        if sorted.is_empty() {
+            return None;
+        }
+
+        let hash = |i: usize| store[sorted[i] as usize].hash;
+        let mut mid = sorted.len() / 2;
+
+        // Lookups default to the right branch on equal hashes, so the
+        // median must be the first of its run of equal hashes.
+        while mid > 0 && hash(mid - 1) == hash(mid) {
+            mid -= 1;
+        }
+
+        let node = &store[sorted[mid] as usize];
+
+        node.left.set(Self::link_balanced(store, &sorted[..mid]));
+        node.right
+            .set(Self::link_balanced(store, &sorted[mid + 1..]));
+
+        NonZeroU32::new(sorted[mid])
+    }
+
+    /// Returns `true` if the longest path from the root of the tree to any
+    /// of its nodes is no longer than `tolerance * log2(len) + 1` nodes,
+    /// with `log2` rounded up.
+    ///
+    /// A perfectly balanced tree passes with a `tolerance` of `1.0`.
+    /// This walks the entire tree, and is `O(n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// assert!(map.is_balanced(3.0));
+    /// ```
+    pub fn is_balanced(&self, tolerance: f64) -> bool {
+        let len = self.store.len();
+        let log2 = match len {
+            0 | 1 => 0,
+            n => usize::BITS - (n - 1).leading_zeros(),
+        };
+
+        self.max_depth() as f64 <= tolerance * log2 as f64 + 1.0
+    }
+
+    /// Same as `is_balanced(2.0)`, which is a good threshold for deciding
+    /// whether lookups have noticeably degraded.
+    #[inline]
+    pub fn is_reasonably_balanced(&self) -> bool {
+        self.is_balanced(2.0)
+    }
+
+    /// Returns the number of nodes on the longest path from the root of the
+    /// tree, which is how many keys the slowest lookup has to compare
+    /// against. This walks the entire tree, and is `O(n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.max_depth(), 0);
+    ///
+    /// map.insert("a", 1);
+    /// assert_eq!(map.max_depth(), 1);
+    /// ```
+    pub fn max_depth(&self) -> usize {
+        self.depth_stats().0
+    }
+
+    /// Returns the average number of nodes on the paths from the root of
+    /// the tree to each of its nodes, which is how many keys a lookup has to
+    /// compare against on average. This walks the entire tree, and is `O(n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<u32, u32> = (0..1000u32).map(|x| (x, x)).collect();
+    ///
+    /// assert!(map.average_depth() <= map.max_depth() as f64);
+    /// ```
+    pub fn average_depth(&self) -> f64 {
+        match self.store.len() {
+            0 => 0.0,
+            len => self.depth_stats().1 as f64 / len as f64,
+        }
+    }
+
+    /// Longest path and sum of all paths from the root of the tree, counted
+    /// in nodes.
+    fn depth_stats(&self) -> (usize, usize) {
+        let root = match self.root_index() {
+            Some(root) => root,
+            None => return (0, 0),
+        };
+
+        let (mut max, mut sum) = (0, 0);
+        // Explicit stack, degenerate trees can be too deep for recursion
+        let mut stack = alloc::vec![(root, 1)];
+
+        while let Some((idx, depth)) = stack.pop() {
+            let node = &self.store[idx];
+
+            max = max.max(depth);
+            sum += depth;
+
+            for link in [&node.left, &node.right].iter() {
+                if let Some(child) = link.get() {
+                    stack.push((child.get() as usize, depth + 1));
+                }
+            }
+        }
+
+        (max, sum)
+    }
+
+    /// Checks that the tree linking the entries is intact: every link is in
+    /// bounds, every node is reachable from the root exactly once, and
+    /// hashes are ordered, with only smaller ones to the left of a node.
+    ///
+    /// This walks the entire tree, and is `O(n)`. A map can't be corrupted
+    /// through its safe API, so this is meant for catching bugs in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// map.sort_keys();
+    /// map.remove(&50);
+    ///
+    /// assert_eq!(map.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), IntegrityError> {
+        let root = match self.root_index() {
+            Some(root) => root,
+            None => return Ok(()),
+        };
+
+        let mut seen = alloc::vec![false; self.store.len()];
+        // Explicit stack, degenerate trees can be too deep for recursion
+        let mut stack = alloc::vec![(root, 0, u64::MAX)];
+
+        while let Some((idx, lo, hi)) = stack.pop() {
+            if core::mem::replace(&mut seen[idx], true) {
+                return Err(IntegrityError::LinkedTwice { index: idx });
+            }
+
+            let node = &self.store[idx];
+
+            if node.hash < lo || node.hash > hi {
+                return Err(IntegrityError::OutOfOrder { index: idx });
+            }
+
+            for (link, lo, hi) in [
+                (&node.left, lo, node.hash.checked_sub(1)),
+                (&node.right, node.hash, Some(hi)),
+            ] {
+                if let Some(child) = link.get() {
+                    let child = child.get() as usize;
+
+                    if child >= self.store.len() {
+                        return Err(IntegrityError::LinkOutOfBounds { index: idx });
+                    }
+
+                    // No hash fits left of a node with a hash of `0`
+                    let hi = hi.ok_or(IntegrityError::OutOfOrder { index: child })?;
+
+                    stack.push((child, lo, hi));
+                }
+            }
+        }
+
+        match seen.iter().position(|&seen| !seen) {
+            Some(index) => Err(IntegrityError::Unreachable { index }),
+            None => Ok(()),
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in the order of the tree,
+    /// which is ascending by hash rather than by insertion. Mostly useful
+    /// for inspecting the shape of the tree.
+    ///
+    /// Walking the tree allocates a stack as deep as the tree itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// assert_eq!(map.iter_tree().count(), 100);
+    /// ```
+    pub fn iter_tree(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        let mut current = self.root_index();
+        let mut stack = alloc::vec::Vec::new();
+
+        core::iter::from_fn(move || loop {
+            while let Some(idx) = current {
+                stack.push(idx);
+                current = self.store[idx].left.get().map(|i| i.get() as usize);
+            }
+
+            let node = &self.store[stack.pop()?];
+
+            current = node.right.get().map(|i| i.get() as usize);
+
+            if let Some(ref value) = node.value {
+                return Some((&node.key, value));
+            }
+        })
+    }
+
+    /// Rebuilds the tree so that it is as balanced as it can be, without
+    /// moving any entries.
+    ///
+    /// The tree is only ever as balanced as the hashes of the keys make it,
+    /// this is for maps that ended up with unlucky ones. Lookups are then
+    /// `O(log n)`, until the tree is rebuilt in insertion order again by
+    /// [`remove`](#method.remove), [`retain`](#method.retain) or
+    /// [`compact`](#method.compact). This is `O(n log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// map.rebalance();
+    ///
+    /// assert!(map.is_balanced(1.0));
+    /// assert!(map.keys().copied().eq(0..100));
+    /// ```
+    pub fn rebalance(&mut self) {
+        self.balance_tree();
+    }
+
+    /// Enables or disables balancing the tree automatically as entries are
+    /// added, be it through [`insert`](#method.insert), the
+    /// [`entry`](#method.entry) API, [`get_or_insert`](#method.get_or_insert)
+    /// and the like. Disabled by default.
+    ///
+    /// When enabled, every time the number of stored entries reaches a power
+    /// of two, adding an entry checks whether the map is still
+    /// [reasonably balanced](#method.is_reasonably_balanced), and
+    /// [`rebalance`](#method.rebalance)s it if not. The checks are `O(n)`
+    /// each, but happen rarely enough to add only `O(1)` to an insert on
+    /// average, while bounding lookups for keys with unlucky hashes.
+    /// Extending the map in bulk checks only once, after the last entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.set_auto_rebalance(true);
+    ///
+    /// for x in 0..1000u32 {
+    ///     map.insert(x, x);
+    /// }
+    ///
+    /// assert!(map.auto_rebalance());
+    /// ```
+    #[inline]
+    pub fn set_auto_rebalance(&mut self, enabled: bool) {
+        self.auto_rebalance = enabled;
+    }
+
+    /// Returns `true` if the tree is balanced automatically on insert, see
+    /// [`set_auto_rebalance`](#method.set_auto_rebalance).
+    #[inline]
+    pub fn auto_rebalance(&self) -> bool {
+        self.auto_rebalance
+    }
+
+    /// Consumes the map, returning an immutable [`FrozenMap`](./struct.FrozenMap.html)
+    /// with the same entries in the same order.
+    ///
+    /// The tree of the frozen map is rebuilt to be balanced, which guarantees
+    /// `O(log n)` lookups regardless of how the entries were inserted, and
+    /// the spare capacity of the map is released.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let frozen = map.freeze();
+    ///
+    /// assert_eq!(frozen.get("a"), Some(&1));
+    /// assert_eq!(frozen["b"], 2);
+    /// assert_eq!(frozen.len(), 2);
+    /// ```
+    pub fn freeze(self) -> FrozenMap<K, V, S> {
+        let live = self
+            .store
+            .iter()
+            .filter(|node| node.value.is_some())
+            .count();
+        let mut store = Store::with_capacity(live);
+
+        for node in self.store {
+            if node.value.is_some() {
+                store.push(node);
+            }
+        }
+
+        let mut map = Map {
+            store,
+            tombstones: 0,
+            auto_rebalance: self.auto_rebalance,
+            balance_on_extend: false,
+            hasher: self.hasher,
+        };
+
+        map.balance_tree();
+
+        FrozenMap { map }
+    }
+
+    /// Creates a new map with the same keys in the same order, with every
+    /// value transformed by `f`.
+    ///
+    /// Keys aren't hashed again and the tree is carried over as is, which
+    /// makes this a lot cheaper than collecting into a new map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let map = map.map_values(|key, value| format!("{}{}", key, value));
+    ///
+    /// assert_eq!(map["a"], "a1");
+    /// assert!(map.values().eq(&["a1", "b2"]));
+    /// ```
+    pub fn map_values<W, F>(self, mut f: F) -> Map<K, W, S>
+    where
+        F: FnMut(&K, V) -> W,
+    {
+        let mut store = Store::with_capacity(self.store.len());
+
+        for node in self.store {
+            let value = match node.value {
+                Some(value) => Some(f(&node.key, value)),
+                None => None,
+            };
+
+            store.push(Node {
+                key: node.key,
+                hash: node.hash,
+                value,
+                left: node.left,
+                right: node.right,
+            });
+        }
+
+        Map {
+            store,
+            tombstones: self.tombstones,
+            auto_rebalance: self.auto_rebalance,
+            balance_on_extend: false,
+            hasher: self.hasher,
+        }
+    }
+}
+
+impl<K, V, S> Clone for Map<K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Map {
+            store: self.store.clone(),
+            tombstones: self.tombstones,
+            auto_rebalance: self.auto_rebalance,
+            balance_on_extend: self.balance_on_extend,
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    /// Overwrites the map with a clone of `source`, reusing the existing
+    /// allocation if it's big enough.
+    fn clone_from(&mut self, source: &Self) {
+        self.store.clone_from(&source.store);
+        self.tombstones = source.tombstones;
+        self.auto_rebalance = source.auto_rebalance;
+        self.balance_on_extend = source.balance_on_extend;
+        self.hasher.clone_from(&source.hasher);
+    }
+}
+
+impl<K, V, S> Default for Map<K, V, S>
+where
+    S: Default,
+{
+    /// Create a new `Map` with a custom hasher.
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> fmt::Debug for Map<K, V, S>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    /// Formats the entries in insertion order, like `{"a": 1, "b": 2}`,
+    /// leaving out the tree and any removed entries.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> Map<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map did not have this key present, `None` is returned.
+    ///
+    /// If the map did have this key present, the value is updated, and the old
+    /// value is returned. The key is not updated, though; this matters for
+    /// types that can be `==` without being identical.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.insert(37, "a"), None);
+    /// assert!(!map.is_empty());
+    ///
+    /// map.insert(37, "b");
+    /// assert_eq!(map.insert(37, "c"), Some("b"));
+    /// assert_eq!(map[&37], "c");
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash_key(&key);
+
+        self.insert_hashed(hash, key, value)
+    }
+
+    /// Inserts a key-value pair into the map, returning the position of the
+    /// entry in insertion order along with the old value, if any.
+    ///
+    /// If the key was already present, its value is updated in place and the
+    /// entry keeps its position. Otherwise the entry is appended at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.insert_full("a", 1), (0, None));
+    /// assert_eq!(map.insert_full("b", 2), (1, None));
+    /// assert_eq!(map.insert_full("a", 3), (0, Some(1)));
+    /// assert_eq!(map.get_index(0), Some((&"a", &3)));
+    /// ```
+    pub fn insert_full(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        let hash = self.hash_key(&key);
+
+        match self.find(hash, &key) {
+            Hit(idx) => {
+                let old = unsafe { self.store.get_unchecked_mut(idx).value.replace(value) };
+
+                (self.position_of(idx), old)
+            }
+            Miss(parent) => {
+                if let Some(parent) = parent {
+                    parent.set(link_to(self.store.len()));
+                }
+
+                self.push_node(Node::new(key, value, hash));
+
+                (self.len() - 1, None)
+            }
+        }
+    }
+
+    /// Inserts a key-value pair into the map, taking the key by reference
+    /// and only converting it into an owned key if it's not present yet.
+    ///
+    /// This avoids allocating when overwriting entries of maps with owned
+    /// keys, such as `String`s, from borrowed ones.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, i32> = Map::new();
+    ///
+    /// assert_eq!(map.insert_borrowed("a", 1), None);
+    /// assert_eq!(map.insert_borrowed("a", 2), Some(1));
+    /// assert_eq!(map["a"], 2);
+    /// ```
+    pub fn insert_borrowed<Q>(&mut self, key: &Q, value: V) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.replace(value) },
+            Miss(parent) => {
+                if let Some(parent) = parent {
+                    parent.set(link_to(self.store.len()));
+                }
+
+                self.push_node(Node::new(key.to_owned(), value, hash));
+
+                None
+            }
+        }
+    }
+
+    /// Inserts a key-value pair into the map, or, if the key is already
+    /// present, combines the new value into the existing one with `merge`.
+    ///
+    /// The key is hashed and looked up only once either way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut counts: Map<&str, u32> = Map::new();
+    ///
+    /// for word in "the cat saw the dog".split(' ') {
+    ///     counts.insert_or_merge(word, 1, |count, one| *count += one);
+    /// }
+    ///
+    /// assert_eq!(counts["the"], 2);
+    /// assert_eq!(counts["cat"], 1);
+    /// ```
+    pub fn insert_or_merge<Q, F>(&mut self, key: Q, value: V, merge: F)
+    where
+        Q: Into<K>,
+        F: FnOnce(&mut V, V),
+    {
+        let key = key.into();
+        let hash = self.hash_key(&key);
+
+        match self.find(hash, &key) {
+            Hit(idx) => {
+                if let Some(existing) = unsafe { self.store.get_unchecked_mut(idx) }.value.as_mut()
+                {
+                    merge(existing, value);
+                }
+            }
+            Miss(parent) => {
+                if let Some(parent) = parent {
+                    parent.set(link_to(self.store.len()));
+                }
+
+                self.push_node(Node::new(key, value, hash));
+            }
+        }
+    }
+
+    /// Extends the map with the entries of an iterator, same as
+    /// [`insert_or_merge`](#method.insert_or_merge) for each entry:
+    /// values of keys that are already present, including ones added
+    /// earlier by the same iterator, are combined with `merge`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut totals = Map::new();
+    /// totals.insert("apples", 3);
+    ///
+    /// totals.extend_merging(vec![("pears", 2), ("apples", 4), ("pears", 1)], |total, n| {
+    ///     *total += n
+    /// });
+    ///
+    /// assert!(totals.iter().eq(vec![(&"apples", &7), (&"pears", &3)]));
+    /// ```
+    pub fn extend_merging<I, F>(&mut self, iter: I, mut merge: F)
+    where
+        I: IntoIterator<Item = (K, V)>,
+        F: FnMut(&mut V, V),
+    {
+        let iter = iter.into_iter();
+
+        self.reserve(iter.size_hint().0);
+
+        for (key, value) in iter {
+            self.insert_or_merge(key, value, &mut merge);
+        }
+    }
+
+    /// Inserts a key-value pair into the map at the given position in
+    /// insertion order, shifting all entries after it by one.
+    ///
+    /// If the key was already present, its value is updated in place and
+    /// the old value is returned, without moving the entry. Otherwise the
+    /// tree is rebuilt, same as with [`remove`](#method.remove), which
+    /// makes this `O(n)`, unless `index` is the end of the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("c", 3);
+    ///
+    /// assert_eq!(map.insert_at(1, "b", 2), None);
+    /// assert!(map.keys().eq(&["a", "b", "c"]));
+    ///
+    /// assert_eq!(map.insert_at(0, "c", 30), Some(3));
+    /// assert!(map.iter().eq(vec![(&"a", &1), (&"b", &2), (&"c", &30)]));
+    /// ```
+    pub fn insert_at(&mut self, index: usize, key: K, value: V) -> Option<V> {
+        assert!(index <= self.len(), "insertion index out of bounds");
+
+        let hash = self.hash_key(&key);
+
+        if index == self.len() {
+            return self.insert_hashed(hash, key, value);
+        }
+
+        match self.find(hash, &key) {
+            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.replace(value) },
+            Miss(_) => {
+                let idx = self.store_index(index);
+
+                self.store.insert(idx, Node::new(key, value, hash));
+                self.rebuild_tree();
+
+                None
+            }
+        }
+    }
+
+    /// Inserts all entries of an array into the map, reserving space for
+    /// all of them up front.
+    ///
+    /// Duplicate keys within the batch behave the same as consecutive
+    /// [`insert`](#method.insert) calls: the last value wins.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert_many([(1, 'a'), (2, 'b'), (1, 'c')]);
+    ///
+    /// assert_eq!(map.len(), 2);
+    /// assert_eq!(map[&1], 'c');
+    /// assert_eq!(map[&2], 'b');
+    /// ```
+    pub fn insert_many<const N: usize>(&mut self, entries: [(K, V); N])
+    where
+        K: Copy,
+        V: Copy,
+    {
+        self.store.reserve(N);
+
+        for (key, value) in entries {
+            let hash = self.hash_key(key);
+
+            self.insert_hashed(hash, key, value);
+        }
+    }
+
+    /// Inserts copies of all entries of a slice into the map, reserving
+    /// space for all of them up front. Same as
+    /// [`insert_many`](#method.insert_many), but for slices of any length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let pairs = vec![(1, 10), (2, 20), (1, 30)];
+    ///
+    /// let mut map = Map::new();
+    /// map.extend_from_slice(&pairs);
+    ///
+    /// assert!(map.iter().eq(vec![(&1, &30), (&2, &20)]));
+    /// ```
+    pub fn extend_from_slice(&mut self, slice: &[(K, V)])
+    where
+        K: Copy,
+        V: Copy,
+    {
+        self.store.reserve(slice.len());
+
+        for &(key, value) in slice {
+            let hash = self.hash_key(key);
+
+            self.insert_hashed(hash, key, value);
+        }
+    }
+
+    /// Merges all entries of `other` into this map, in insertion order of
+    /// `other`, resolving keys present in both maps with `policy`:
+    ///
+    /// + `MergePolicy::KeepExisting` keeps the value already in this map and
+    ///   drops the incoming one.
+    /// + `MergePolicy::TakeIncoming` replaces the value in this map with the
+    ///   incoming one, same as `insert` would.
+    /// + `MergePolicy::Error` merges nothing if any key is present in both
+    ///   maps, returning the first such key in the insertion order of
+    ///   `other` as an error.
+    ///
+    /// Either way, entries already in this map keep their position, and new
+    /// entries are appended at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::{Map, MergePolicy};
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let mut other = Map::new();
+    /// other.insert("b", 20);
+    /// other.insert("c", 30);
+    ///
+    /// let err = map.merge(other.clone(), MergePolicy::Error).unwrap_err();
+    /// assert_eq!(err.key, "b");
+    /// assert_eq!(map.len(), 2);
+    ///
+    /// map.merge(other.clone(), MergePolicy::KeepExisting).unwrap();
+    /// assert_eq!(map["b"], 2);
+    /// assert_eq!(map["c"], 30);
+    ///
+    /// map.merge(other, MergePolicy::TakeIncoming).unwrap();
+    /// assert_eq!(map["b"], 20);
+    /// assert!(map.keys().eq(&["a", "b", "c"]));
+    /// ```
+    pub fn merge(&mut self, other: Map<K, V, S>, policy: MergePolicy) -> Result<(), MergeError<K>> {
+        if policy == MergePolicy::Error {
+            let collision = other
+                .store
+                .iter()
+                .position(|node| node.value.is_some() && self.contains_key(&node.key));
+
+            if let Some(idx) = collision {
+                let key = other.store.into_iter().nth(idx).map(|node| node.key);
+
+                return Err(MergeError { key: key.unwrap() });
+            }
+        }
+
+        self.store.reserve(other.len());
+
+        for node in other.store {
+            let value = match node.value {
+                Some(value) => value,
+                None => continue,
+            };
+
+            // The other map's hash builder might be seeded differently,
+            // so its hashes can't be reused
+            let hash = self.hash_key(&node.key);
+
+            if policy == MergePolicy::KeepExisting {
+                match self.find(hash, &node.key) {
+                    Hit(_) => {}
+                    Miss(parent) => {
+                        if let Some(parent) = parent {
+                            parent.set(link_to(self.store.len()));
+                        }
+
+                        self.push_node(Node::new(node.key, value, hash));
+                    }
+                }
+            } else {
+                self.insert_hashed(hash, node.key, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves all entries of `other` into this map, in insertion order of
+    /// `other`, leaving `other` empty but with its capacity intact.
+    ///
+    /// Keys present in both maps take the value from `other`, while keeping
+    /// their position in this map, same as `insert` would.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let mut other = Map::new();
+    /// other.insert("c", 30);
+    /// other.insert("b", 20);
+    ///
+    /// map.append(&mut other);
+    ///
+    /// assert!(other.is_empty());
+    /// assert!(map.iter().eq(vec![(&"a", &1), (&"b", &20), (&"c", &30)]));
+    /// ```
+    pub fn append(&mut self, other: &mut Map<K, V, S>) {
+        self.reserve(other.len());
+
+        for (key, value) in other.drain() {
+            self.insert(key, value);
+        }
+    }
+
+    /// Moves all entries of `other` into this map, in insertion order of
+    /// `other`. Same as [`append`](#method.append), but consumes `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// let mut other = Map::new();
+    /// other.insert("a", 10);
+    /// other.insert("b", 20);
+    ///
+    /// map.extend_from_map(other);
+    ///
+    /// assert!(map.iter().eq(vec![(&"a", &10), (&"b", &20)]));
+    /// ```
+    pub fn extend_from_map(&mut self, other: Map<K, V, S>) {
+        let _ = self.merge(other, MergePolicy::TakeIncoming);
+    }
+
+    /// Consumes the map, splitting it in two by the predicate `f`: the first
+    /// map gets every entry for which `f` returned `true`, the second all
+    /// the rest. Both keep the relative insertion order of their entries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    /// map.insert("d", 4);
+    ///
+    /// let (odd, even) = map.partition(|_, value| value % 2 == 1);
+    ///
+    /// assert!(odd.keys().eq(&["a", "c"]));
+    /// assert!(even.keys().eq(&["b", "d"]));
+    /// ```
+    pub fn partition<F>(self, mut f: F) -> (Map<K, V, S>, Map<K, V, S>)
+    where
+        F: FnMut(&K, &V) -> bool,
+        S: Clone,
+    {
+        let len = self.len();
+        let mut left = Map::with_capacity_and_hasher(len, self.hasher.clone());
+        let mut right = Map::with_capacity_and_hasher(len, self.hasher.clone());
+
+        for (key, value) in self {
+            if f(&key, &value) {
+                left.insert(key, value);
+            } else {
+                right.insert(key, value);
+            }
+        }
+
+        (left, right)
+    }
+
+    /// Creates a `Map` from an iterator of key-value pairs, same as
+    /// [`FromIterator`], but only keeping the entries for which `keep`
+    /// returns `true`.
+    ///
+    /// This is cheaper than collecting all entries and calling
+    /// [`retain`](#method.retain) after, which has to rebuild the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<u32, u32> =
+    ///     Map::from_iter_filtered((0..10u32).map(|x| (x, x * x)), |_, &square| square > 20);
+    ///
+    /// assert!(map.keys().eq(&[5, 6, 7, 8, 9]));
+    /// ```
+    ///
+    /// [`FromIterator`]: https://doc.rust-lang.org/std/iter/trait.FromIterator.html
+    pub fn from_iter_filtered<IK, IV, I, F>(iter: I, mut keep: F) -> Self
+    where
+        IK: Into<K>,
+        IV: Into<V>,
+        I: IntoIterator<Item = (IK, IV)>,
+        F: FnMut(&K, &V) -> bool,
+        S: Default,
+    {
+        let iter = iter.into_iter();
+        let mut map = Map::with_capacity_and_hasher(iter.size_hint().0, S::default());
+
+        for (key, value) in iter {
+            let (key, value) = (key.into(), value.into());
+
+            if keep(&key, &value) {
+                map.insert(key, value);
+            }
+        }
+
+        map.balance_tree();
+        map
+    }
+
+    /// Creates a `Map` from an iterator of key-value pairs, same as
+    /// [`FromIterator`], but failing on the first key that is already
+    /// present instead of overwriting its value.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`DuplicateKeyError`] carrying the offending key if the
+    /// iterator yields the same key more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<&str, i32> = Map::try_from_iter(vec![("a", 1), ("b", 2)]).unwrap();
+    /// assert_eq!(map["b"], 2);
+    ///
+    /// let err = Map::<&str, i32>::try_from_iter(vec![("a", 1), ("b", 2), ("a", 3)]).unwrap_err();
+    /// assert_eq!(err.key, "a");
+    /// ```
+    ///
+    /// [`FromIterator`]: https://doc.rust-lang.org/std/iter/trait.FromIterator.html
+    /// [`DuplicateKeyError`]: ./struct.DuplicateKeyError.html
+    pub fn try_from_iter<IK, IV, I>(iter: I) -> Result<Self, DuplicateKeyError<K>>
+    where
+        IK: Into<K>,
+        IV: Into<V>,
+        I: IntoIterator<Item = (IK, IV)>,
+        S: Default,
+    {
+        let iter = iter.into_iter();
+        let mut map: Self = Map::with_capacity_and_hasher(iter.size_hint().0, S::default());
+
+        for (key, value) in iter {
+            let key = key.into();
+            let hash = map.hash_key(&key);
+
+            match map.find(hash, &key) {
+                Hit(_) => return Err(DuplicateKeyError { key }),
+                Miss(parent) => {
+                    if let Some(parent) = parent {
+                        parent.set(link_to(map.store.len()));
+                    }
+
+                    map.store.push(Node::new(key, value.into(), hash));
+                }
+            }
+        }
+
+        map.balance_tree();
+        Ok(map)
+    }
+
+    /// Creates a `Map` from an iterator of key-value pairs, same as
+    /// [`FromIterator`], but keeping the first value of every key instead
+    /// of the last one.
+    ///
+    /// The two differ only in which value survives a duplicate key:
+    ///
+    /// + `from_iter` (or `collect`) is last-wins: later values overwrite
+    ///   earlier ones.
+    /// + `from_iter_keep_first` is first-wins: later values are dropped.
+    ///
+    /// Either way, the entry takes the position of the first occurrence of
+    /// its key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let pairs = vec![("a", 1), ("b", 2), ("a", 3)];
+    ///
+    /// let first: Map<&str, i32> = Map::from_iter_keep_first(pairs.clone());
+    /// let last: Map<&str, i32> = pairs.into_iter().collect();
+    ///
+    /// assert!(first.iter().eq(vec![(&"a", &1), (&"b", &2)]));
+    /// assert!(last.iter().eq(vec![(&"a", &3), (&"b", &2)]));
+    /// ```
+    ///
+    /// [`FromIterator`]: https://doc.rust-lang.org/std/iter/trait.FromIterator.html
+    pub fn from_iter_keep_first<IK, IV, I>(iter: I) -> Self
+    where
+        IK: Into<K>,
+        IV: Into<V>,
+        I: IntoIterator<Item = (IK, IV)>,
+        S: Default,
+    {
+        let iter = iter.into_iter();
+        let mut map: Self = Map::with_capacity_and_hasher(iter.size_hint().0, S::default());
+
+        for (key, value) in iter {
+            let key = key.into();
+            let hash = map.hash_key(&key);
+
+            if let Miss(parent) = map.find(hash, &key) {
+                if let Some(parent) = parent {
+                    parent.set(link_to(map.store.len()));
+                }
+
+                map.store.push(Node::new(key, value.into(), hash));
+            }
+        }
+
+        map.balance_tree();
+        map
+    }
+
+    /// Creates a `Map` from an iterator of key-value pairs already sorted
+    /// by key, keeping the iteration order.
+    ///
+    /// Since equal keys can only be next to each other, duplicates are
+    /// found without a lookup per entry, with later values overwriting
+    /// earlier ones. The tree is then balanced once all entries are in,
+    /// which makes this `O(n log n)` without walking the tree on every
+    /// insert, as [`FromIterator`] does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not sorted in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<&str, u32> = Map::from_sorted_iter(vec![("a", 1), ("b", 2), ("b", 3), ("c", 4)]);
+    ///
+    /// assert!(map.iter().eq(vec![(&"a", &1), (&"b", &3), (&"c", &4)]));
+    /// ```
+    ///
+    /// [`FromIterator`]: https://doc.rust-lang.org/std/iter/trait.FromIterator.html
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Ord,
+        S: Default,
+    {
+        let iter = iter.into_iter();
+        let mut map: Self = Map::with_capacity_and_hasher(iter.size_hint().0, S::default());
+
+        for (key, value) in iter {
+            if let Some(last) = map.store.last_mut() {
+                match last.key.cmp(&key) {
+                    Ordering::Less => {}
+                    Ordering::Equal => {
+                        last.value = Some(value);
+                        continue;
+                    }
+                    Ordering::Greater => panic!("keys passed to from_sorted_iter are not sorted"),
+                }
+            }
+
+            let hash = map.hash_key(&key);
+
+            map.store.push(Node::new(key, value, hash));
+        }
+
+        map.balance_tree();
+        map
+    }
+
+    /// Clones all entries of `other` into a new map using `hasher`, in
+    /// insertion order. Since every node stores the hash of its key, the
+    /// hashes and the tree have to be computed anew, which makes this
+    /// `O(n log n)` rather than a plain copy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let seeded = Map::with_hasher_from(&map, RandomState::new());
+    ///
+    /// assert!(seeded.iter().eq(map.iter()));
+    /// assert_eq!(seeded["b"], 2);
+    /// ```
+    pub fn with_hasher_from<H>(other: &Map<K, V, H>, hasher: S) -> Self
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut map = Map::with_capacity_and_hasher(other.len(), hasher);
+
+        for (key, value) in other {
+            map.insert(key.clone(), value.clone());
+        }
+
+        map
+    }
+
+    #[inline]
+    fn insert_hashed(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        match self.find(hash, &key) {
+            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.replace(value) },
+            Miss(parent) => {
+                if let Some(parent) = parent {
+                    parent.set(link_to(self.store.len()));
+                }
+
+                self.push_node(Node::new(key, value, hash));
+
+                None
+            }
+        }
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    /// Returns a reference to the value corresponding to the key, or
+    /// `KeyNotFound` if it's not in the map.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::{Map, KeyNotFound};
+    ///
+    /// fn port(config: &Map<&str, u16>) -> Result<u16, KeyNotFound> {
+    ///     let port = config.try_get("port")?;
+    ///
+    ///     Ok(*port)
+    /// }
+    ///
+    /// let mut config = Map::new();
+    /// assert_eq!(port(&config), Err(KeyNotFound));
+    ///
+    /// config.insert("port", 8080);
+    /// assert_eq!(port(&config), Ok(8080));
+    /// ```
+    #[inline]
+    pub fn try_get<Q>(&self, key: &Q) -> Result<&V, KeyNotFound>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).ok_or(KeyNotFound)
+    }
+
+    /// Returns a clone of the value corresponding to the key, or `default`
+    /// if it's not in the map. The map is left untouched either way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut config = Map::new();
+    /// config.insert("port", 8080);
+    ///
+    /// assert_eq!(config.get_or("port", 80), 8080);
+    /// assert_eq!(config.get_or("timeout", 30), 30);
+    /// assert_eq!(config.len(), 1);
+    /// ```
+    #[inline]
+    pub fn get_or<Q>(&self, key: &Q, default: V) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.get(key).cloned().unwrap_or(default)
+    }
+
+    /// Returns a clone of the value corresponding to the key, or the result
+    /// of calling `f` if it's not in the map. The map is left untouched
+    /// either way, and `f` is only called on a miss.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut config = Map::new();
+    /// config.insert("name", String::from("ordnung"));
+    ///
+    /// assert_eq!(config.get_or_else("name", || unreachable!()), "ordnung");
+    /// assert_eq!(config.get_or_else("user", || "root".into()), "root");
+    /// assert!(!config.contains_key("user"));
+    /// ```
+    #[inline]
+    pub fn get_or_else<Q, F>(&self, key: &Q, f: F) -> V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+        F: FnOnce() -> V,
+    {
+        self.get(key).cloned().unwrap_or_else(f)
+    }
+
+    /// Returns a reference to the value corresponding to the key, along with the original key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("Content-Type"), 1);
+    ///
+    /// let (key, value) = map.get_key_value("Content-Type").unwrap();
+    ///
+    /// assert_eq!(key, &String::from("Content-Type"));
+    /// assert_eq!(value, &1);
+    /// assert_eq!(map.get_key_value("Accept"), None);
+    /// ```
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => {
+                let node = unsafe { self.store.get_unchecked(idx) };
+
+                node.value.as_ref().map(|v| (&node.key, v))
+            }
+            Miss(_) => None,
+        }
+    }
+
+    /// Returns the position of the entry in insertion order, along with the
+    /// key and a reference to the value corresponding to the key.
+    ///
+    /// The position can be used with [`get_index`](#method.get_index) to
+    /// access the entry again without hashing the key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.get_full("b"), Some((1, &"b", &2)));
+    /// assert_eq!(map.get_full("c"), None);
+    /// ```
+    pub fn get_full<Q>(&self, key: &Q) -> Option<(usize, &K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => {
+                let node = unsafe { self.store.get_unchecked(idx) };
+
+                node.value
+                    .as_ref()
+                    .map(|v| (self.position_of(idx), &node.key, v))
+            }
+            Miss(_) => None,
+        }
+    }
+
+    /// Returns the position of the entry in insertion order, along with the
+    /// key and a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// if let Some((idx, _, value)) = map.get_full_mut("a") {
+    ///     assert_eq!(idx, 0);
+    ///     *value = 10;
+    /// }
+    /// assert_eq!(map["a"], 10);
+    /// ```
+    pub fn get_full_mut<Q>(&mut self, key: &Q) -> Option<(usize, &K, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => {
+                let position = self.position_of(idx);
+                let Node { key, value, .. } = unsafe { self.store.get_unchecked_mut(idx) };
+
+                value.as_mut().map(move |value| (position, &*key, value))
+            }
+            Miss(_) => None,
+        }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but Hash and Eq
+    /// on the borrowed form must match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(1, "a");
+    /// if let Some(x) = map.get_mut(&1) {
+    ///     *x = "b";
+    /// }
+    /// assert_eq!(map[&1], "b");
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.as_mut() },
+            Miss(_) => None,
+        }
+    }
+
+    /// Returns mutable references to the values of several keys at once.
+    ///
+    /// Every key that isn't present in the map yields `None` at its
+    /// position in the returned array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any two keys refer to the same entry, as that would hand
+    /// out aliasing mutable references.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 10);
+    /// map.insert("b", 5);
+    ///
+    /// if let [Some(a), Some(b), None] = map.get_disjoint_mut(["a", "b", "c"]) {
+    ///     *a -= 3;
+    ///     *b += 3;
+    /// }
+    ///
+    /// assert_eq!(map["a"], 7);
+    /// assert_eq!(map["b"], 8);
+    /// ```
+    pub fn get_disjoint_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> [Option<&mut V>; N]
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let indices = keys.map(|key| match self.find(self.hash_key(key), key) {
+            Hit(idx) => Some(idx),
+            Miss(_) => None,
+        });
+
+        for (i, idx) in indices.iter().enumerate() {
+            if idx.is_some() && indices[..i].contains(idx) {
+                panic!("duplicate keys passed to get_disjoint_mut");
+            }
+        }
+
+        let store = self.store.as_mut_ptr();
+
+        // Indices are distinct and in bounds, so the references can't alias
+        indices.map(|idx| idx.and_then(|idx| unsafe { (*store.add(idx)).value.as_mut() }))
+    }
+
+    /// Returns references to the values of several keys at once, with
+    /// `None` at the position of every key that isn't present in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.get_many(["b", "c", "a"]), [Some(&2), None, Some(&1)]);
+    /// ```
+    pub fn get_many<Q, const N: usize>(&self, keys: [&Q; N]) -> [Option<&V>; N]
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        keys.map(|key| self.get(key))
+    }
+
+    /// Returns mutable references to the values of two keys at once, or
+    /// `None` if either key isn't present in the map, or both keys refer
+    /// to the same entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 10);
+    /// map.insert("b", 5);
+    ///
+    /// if let Some((a, b)) = map.get2_mut("a", "b") {
+    ///     core::mem::swap(a, b);
+    /// }
+    ///
+    /// assert_eq!(map["a"], 5);
+    /// assert_eq!(map["b"], 10);
+    /// assert_eq!(map.get2_mut("a", "a"), None);
+    /// assert_eq!(map.get2_mut("a", "c"), None);
+    /// ```
+    pub fn get2_mut<Q>(&mut self, a: &Q, b: &Q) -> Option<(&mut V, &mut V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let a = match self.find(self.hash_key(a), a) {
+            Hit(idx) => idx,
+            Miss(_) => return None,
+        };
+        let b = match self.find(self.hash_key(b), b) {
+            Hit(idx) => idx,
+            Miss(_) => return None,
+        };
+
+        if a == b {
+            return None;
+        }
+
+        let store = self.store.as_mut_ptr();
+
+        // Both indices are in bounds and distinct, so the references can't alias
+        unsafe {
+            let a = (*store.add(a)).value.as_mut()?;
+            let b = (*store.add(b)).value.as_mut()?;
+
+            Some((a, b))
+        }
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.contains_key(&1), true);
+    /// assert_eq!(map.contains_key(&2), false);
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => unsafe { self.store.get_unchecked(idx).value.is_some() },
+            Miss(_) => false,
+        }
+    }
+
+    /// Returns the hash stored for the entry with the given key, or `None`
+    /// if the key is not present in the map.
+    ///
+    /// This is the same 64-bit hash the map's `BuildHasher` produces for the
+    /// key, and which is used to place the entry in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// assert_eq!(map.get_hash("a"), map.hash_at(0));
+    /// assert_eq!(map.get_hash("b"), None);
+    /// ```
+    pub fn get_hash<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => {
+                let node = unsafe { self.store.get_unchecked(idx) };
+
+                node.value.as_ref().map(|_| node.hash)
+            }
+            Miss(_) => None,
+        }
+    }
+
+    /// Get a mutable reference to entry at key. Inserts a new entry by
+    /// calling `F` if absent.
+    ///
+    /// `F` can't reach the map while it is borrowed here, and should it
+    /// panic the map is left untouched.
+    // TODO: Replace with entry API
+    pub fn get_or_insert<F>(&mut self, key: K, fill: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        let hash = self.hash_key(&key);
+
+        match self.find(hash, &key) {
+            Hit(idx) => {
+                let node = unsafe { self.store.get_unchecked_mut(idx) };
+
+                if node.value.is_none() {
+                    node.value = Some(fill());
+                }
+
+                node.value.as_mut().unwrap()
+            }
+            Miss(parent) => {
+                let idx = self.store.len();
+
+                // Fill before linking, a panic in `fill` must not leave
+                // the parent pointing past the end of the store.
+                let node = Node::new(key, fill(), hash);
+
+                if let Some(parent) = parent {
+                    parent.set(link_to(idx));
+                }
+
+                self.push_node(node);
+
+                self.store[idx].value.as_mut().unwrap()
+            }
+        }
+    }
+
+    /// Same as [`get_or_insert`](#method.get_or_insert), but also returns
+    /// the position of the entry in insertion order, which can be used with
+    /// [`get_index`](#method.get_index) without hashing the key again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    ///
+    /// let (idx, value) = map.get_or_insert_full("b", || 2);
+    /// *value += 10;
+    /// assert_eq!(idx, 1);
+    ///
+    /// assert_eq!(map.get_or_insert_full("a", || 3), (0, &mut 1));
+    /// assert_eq!(map.get_index(1), Some((&"b", &12)));
+    /// ```
+    pub fn get_or_insert_full<F>(&mut self, key: K, fill: F) -> (usize, &mut V)
+    where
+        F: FnOnce() -> V,
+    {
+        let hash = self.hash_key(&key);
+
+        match self.find(hash, &key) {
+            Hit(idx) => {
+                let position = self.position_of(idx);
+
+                (position, unsafe {
+                    self.store.get_unchecked_mut(idx).value.as_mut().unwrap()
+                })
+            }
+            Miss(parent) => {
+                let idx = self.store.len();
+
+                // Fill before linking, same as in `get_or_insert`.
+                let node = Node::new(key, fill(), hash);
+
+                if let Some(parent) = parent {
+                    parent.set(link_to(idx));
+                }
+
+                self.push_node(node);
+
+                (self.len() - 1, self.store[idx].value.as_mut().unwrap())
+            }
+        }
+    }
+
+    /// Same as [`get_or_insert`](#method.get_or_insert), but takes the key
+    /// by reference, only converting it into an owned key if it's not
+    /// present yet.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<String, Vec<u32>> = Map::new();
+    ///
+    /// map.get_or_insert_ref("a", Vec::new).push(1);
+    /// map.get_or_insert_ref("a", Vec::new).push(2);
+    ///
+    /// assert_eq!(map["a"], [1, 2]);
+    /// ```
+    pub fn get_or_insert_ref<Q, F>(&mut self, key: &Q, fill: F) -> &mut V
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+        F: FnOnce() -> V,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value.as_mut().unwrap() },
+            Miss(parent) => {
+                let idx = self.store.len();
+
+                // Fill before linking, same as in `get_or_insert`.
+                let node = Node::new(key.to_owned(), fill(), hash);
+
+                if let Some(parent) = parent {
+                    parent.set(link_to(idx));
+                }
+
+                self.push_node(node);
+
+                self.store[idx].value.as_mut().unwrap()
+            }
+        }
+    }
+
+    /// Same as [`get_or_insert`](#method.get_or_insert), but with a `fill`
+    /// that can fail. Should it return an error, the map is left untouched
+    /// and the error is returned. `fill` is not called if the key is already
+    /// present.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<&str, u32> = Map::new();
+    ///
+    /// assert_eq!(map.get_or_try_insert_with("a", || "1".parse()), Ok(&mut 1));
+    /// assert!(map.get_or_try_insert_with("b", || "x".parse()).is_err());
+    /// assert_eq!(map.get_or_try_insert_with("a", || "x".parse()), Ok(&mut 1));
+    ///
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn get_or_try_insert_with<Q, E, F>(&mut self, key: Q, fill: F) -> Result<&mut V, E>
+    where
+        Q: Into<K>,
+        F: FnOnce() -> Result<V, E>,
+    {
+        let key = key.into();
+        let hash = self.hash_key(&key);
+
+        match self.find(hash, &key) {
+            Hit(idx) => Ok(unsafe { self.store.get_unchecked_mut(idx).value.as_mut().unwrap() }),
+            Miss(parent) => {
+                let idx = self.store.len();
+
+                // Fill before linking, so that an error leaves the tree
+                // exactly as it was.
+                let node = Node::new(key, fill()?, hash);
+
+                if let Some(parent) = parent {
+                    parent.set(link_to(idx));
+                }
+
+                self.push_node(node);
+
+                Ok(self.store[idx].value.as_mut().unwrap())
+            }
+        }
+    }
+
+    /// Returns how expensive it would be to [`remove`](#method.remove) the
+    /// given key from the map.
+    ///
+    /// Removing the most recently inserted entry only unlinks it from the
+    /// tree, which is as cheap as a lookup. Any other entry is followed by
+    /// entries that have to shift to the left, after which the whole tree
+    /// is rebuilt, which is `O(n)`. Removing a key that's not present is
+    /// just a lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::{Map, RemovalKind};
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.remove_cost_hint("a"), RemovalKind::Rebuild);
+    /// assert_eq!(map.remove_cost_hint("b"), RemovalKind::Cheap);
+    /// assert_eq!(map.remove_cost_hint("c"), RemovalKind::Cheap);
+    /// ```
+    pub fn remove_cost_hint<Q>(&self, key: &Q) -> RemovalKind
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => self.removal_kind(idx),
+            Miss(_) => RemovalKind::Cheap,
+        }
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key
+    /// was previously in the map.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    ///
+    /// Unless the key is the most recently inserted one, this rebuilds the
+    /// tree, see [`remove_cost_hint`](#method.remove_cost_hint).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove(&1), Some("a"));
+    /// assert_eq!(map.remove(&1), None);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// Removes a key from the map, returning the stored key and value if the
+    /// key was previously in the map.
+    ///
+    /// The key may be any borrowed form of the map's key type, but `Hash` and
+    /// `Eq` on the borrowed form must match those for the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("a"), 1);
+    /// assert_eq!(map.remove_entry("a"), Some((String::from("a"), 1)));
+    /// assert_eq!(map.remove_entry("a"), None);
+    /// ```
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => Some(self.remove_index(idx)),
+            Miss(_) => None,
+        }
+    }
+
+    /// Removes a key from the map by swapping its entry with the last one,
+    /// returning the value at the key if the key was previously in the map.
+    ///
+    /// **This does not preserve insertion order**: the last entry is moved
+    /// into the position of the removed one, same as `Vec::swap_remove`.
+    /// The first entry is the root of the tree though, so removing it moves
+    /// the entry taking its place in the tree to the front instead, and the
+    /// last entry into the position that one left.
+    ///
+    /// In exchange, only the links to the entries involved are updated,
+    /// instead of rebuilding the whole tree like [`remove`](#method.remove)
+    /// does, which makes this about as cheap as a lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("c", 3);
+    /// map.insert("d", 4);
+    ///
+    /// assert_eq!(map.swap_remove("b"), Some(2));
+    /// assert_eq!(map.swap_remove("b"), None);
+    ///
+    /// // "d" took the place of "b"
+    /// assert!(map.keys().eq(&["a", "d", "c"]));
+    /// ```
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => Some(self.swap_remove_index(idx).1),
+            Miss(_) => None,
+        }
+    }
+
+    /// Removes a key from the map, returning the value at the key if the key
+    /// was previously in the map, without rebuilding the tree.
+    ///
+    /// The entry is only marked as removed, which makes this as cheap as a
+    /// lookup instead of `O(n)`, but it keeps taking up space in the map until
+    /// it's [`compact`](#method.compact)ed. Inserting the same key again
+    /// appends a new entry at the end, same as it would after `remove`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// assert_eq!(map.remove_lazy("a"), Some(1));
+    /// assert_eq!(map.remove_lazy("a"), None);
+    /// assert_eq!(map.len(), 1);
+    ///
+    /// map.insert("a", 3);
+    ///
+    /// assert!(map.iter().eq(vec![(&"b", &2), (&"a", &3)]));
+    /// assert_eq!(map.tombstone_count(), 1);
+    /// ```
+    pub fn remove_lazy<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => {
+                self.tombstones += 1;
+
+                unsafe { self.store.get_unchecked_mut(idx).value.take() }
+            }
+            Miss(_) => None,
+        }
+    }
+
+    /// Removes all of the given keys from the map, returning how many of
+    /// them were present.
+    ///
+    /// Unlike calling [`remove`](#method.remove) for each key, which
+    /// rebuilds the tree every time, this removes the entries lazily
+    /// first, then [`compact`](#method.compact)s the map once, making
+    /// it `O(n + k log n)` for `k` keys instead of `O(k * n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+    ///
+    /// assert_eq!(map.remove_all(&[1, 3, 5, 42]), 3);
+    ///
+    /// assert_eq!(map.len(), 7);
+    /// assert!(map.keys().eq(&[0, 2, 4, 6, 7, 8, 9]));
+    /// ```
+    pub fn remove_all<'a, Q, I>(&mut self, keys: I) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized + 'a,
+        I: IntoIterator<Item = &'a Q>,
+    {
+        let removed = keys
+            .into_iter()
+            .filter(|key| self.remove_lazy(*key).is_some())
+            .count();
+
+        self.compact();
+
+        removed
+    }
+
+    /// Retains only the elements specified by the predicate, allowing the
+    /// predicate to mutate the keys as well as the values.
+    ///
+    /// This is the most expensive of the retain variants: every retained key
+    /// is hashed again and the whole tree is rebuilt, but it's the only way
+    /// to change keys in place. Should two keys become equal, the value of
+    /// the latter overwrites the former, at the position of the former.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut headers = Map::new();
+    /// headers.insert(String::from(" Accept"), 1);
+    /// headers.insert(String::from("  "), 2);
+    /// headers.insert(String::from("Host "), 3);
+    ///
+    /// headers.retain_keys_mut(|key, _| {
+    ///     *key = key.trim().to_string();
+    ///     !key.is_empty()
+    /// });
+    ///
+    /// assert_eq!(headers.len(), 2);
+    /// assert_eq!(headers["Accept"], 1);
+    /// assert_eq!(headers["Host"], 3);
+    /// ```
+    pub fn retain_keys_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut K, &mut V) -> bool,
+    {
+        let capacity = self.store.capacity();
+        let old = core::mem::replace(&mut self.store, Store::with_capacity(capacity));
+
+        self.tombstones = 0;
+
+        for mut node in old {
+            let keep = match node.value {
+                Some(ref mut value) => f(&mut node.key, value),
+                None => false,
+            };
+
+            if !keep {
+                continue;
+            }
+
+            let hash = self.hash_key(&node.key);
+
+            match self.find(hash, &node.key) {
+                Hit(idx) => unsafe { self.store.get_unchecked_mut(idx).value = node.value },
+                Miss(parent) => {
+                    if let Some(parent) = parent {
+                        parent.set(link_to(self.store.len()));
+                    }
+
+                    node.hash = hash;
+                    node.left.set(None);
+                    node.right.set(None);
+
+                    self.push_node(node);
+                }
+            }
+        }
+    }
+
+    /// Applies `f` to every key in the map, in insertion order.
+    ///
+    /// Since the hashes of the keys change, every key is hashed again and
+    /// the tree is rebuilt, which makes this `O(n log n)`. Should two keys
+    /// become equal, the value of the latter overwrites the former, at the
+    /// position of the former, same as with
+    /// [`retain_keys_mut`](#method.retain_keys_mut).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("Accept"), 1);
+    /// map.insert(String::from("HOST"), 2);
+    /// map.insert(String::from("accept"), 3);
+    ///
+    /// map.update_keys(|key| key.make_ascii_lowercase());
+    ///
+    /// assert!(map.keys().eq(&["accept", "host"]));
+    /// assert_eq!(map["accept"], 3);
+    /// ```
+    pub fn update_keys<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut K),
+    {
+        self.retain_keys_mut(|key, _| {
+            f(key);
+            true
+        });
+    }
+
+    #[inline]
+    fn find<Q>(&self, hash: u64, key: &Q) -> FindResult<'_>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.find_with(hash, |stored| stored.borrow() == key)
+    }
+
+    /// Same as `find`, but matches keys of equal hash with `is_match`
+    /// instead of `==`.
+    #[inline]
+    fn find_with<F>(&self, hash: u64, mut is_match: F) -> FindResult<'_>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        if self.store.is_empty() {
+            return Miss(None);
+        }
+
+        let mut idx = 0;
+
+        loop {
+            let node = unsafe { self.store.get_unchecked(idx) };
+
+            #[cfg(feature = "prefetch")]
+            prefetch_children(&self.store, node);
+
+            // Keys with colliding hashes are always stored to the right,
+            // as are keys inserted again after being removed lazily.
+            let link = if hash < node.hash {
+                &node.left
+            } else if hash == node.hash && node.value.is_some() && is_match(&node.key) {
+                return Hit(idx);
+            } else {
+                &node.right
+            };
+
+            match link.get() {
+                Some(i) => idx = i.get() as usize,
+                None => return Miss(Some(link)),
+            }
+        }
+    }
+
+    /// Returns a reference to the value of the first entry with the given
+    /// `hash` for which `is_match` returns `true`, walking the tree the
+    /// same way as [`get`](#method.get) does.
+    ///
+    /// This allows looking up keys by something other than `==`, as long
+    /// as the hash is computed the same way: for example case-insensitive
+    /// string keys, hashed in lowercase on insertion, can be looked up by
+    /// hashing the lowercase form of the probe and comparing keys with
+    /// `eq_ignore_ascii_case`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use std::hash::BuildHasher;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("content-type"), 1);
+    ///
+    /// let probe = "Content-Type";
+    /// let hash = map.hasher().hash_one(probe.to_ascii_lowercase());
+    ///
+    /// assert_eq!(map.get_by(hash, |key| key.eq_ignore_ascii_case(probe)), Some(&1));
+    /// assert_eq!(map.get_by(hash, |key| key == probe), None);
+    /// ```
+    pub fn get_by<F>(&self, hash: u64, is_match: F) -> Option<&V>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        match self.find_with(hash, is_match) {
+            Hit(idx) => unsafe { self.store.get_unchecked(idx).value.as_ref() },
+            Miss(_) => None,
+        }
+    }
+
+    /// Returns the position in insertion order of the first entry with the
+    /// given `hash` for which `is_match` returns `true`, walking the tree
+    /// the same way as [`get_by`](#method.get_by) does.
+    ///
+    /// The position can be used with [`get_index`](#method.get_index) and
+    /// [`get_index_mut`](#method.get_index_mut) to access the entry. To
+    /// insert entries found missing this way, use the
+    /// [`raw_entry_mut`](#method.raw_entry_mut) API.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use std::hash::BuildHasher;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("accept"), 1);
+    /// map.insert(String::from("content-type"), 2);
+    ///
+    /// let probe = "Content-Type";
+    /// let hash = map.hasher().hash_one(probe.to_ascii_lowercase());
+    /// let index = map.locate(hash, |key| key.eq_ignore_ascii_case(probe));
+    ///
+    /// assert_eq!(index, Some(1));
+    ///
+    /// if let Some((_, value)) = map.get_index_mut(1) {
+    ///     *value += 10;
+    /// }
+    ///
+    /// assert_eq!(map["content-type"], 12);
+    /// ```
+    pub fn locate<F>(&self, hash: u64, is_match: F) -> Option<usize>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        match self.find_with(hash, is_match) {
+            Hit(idx) => Some(self.position_of(idx)),
+            Miss(_) => None,
+        }
+    }
+
+    #[inline]
+    fn hash_key<Q: Hash>(&self, key: Q) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    /// Creates a raw entry builder for the HashMap.
+    ///
+    /// Raw entries provide the lowest level of control for searching and
+    /// manipulating a map. They must be manually initialized with a hash and
+    /// then manually searched. After this, insertions into a vacant entry
+    /// still require an owned key to be provided.
+    ///
+    /// Raw entries are useful for such exotic situations as:
+    ///
+    /// * Hash memoization
+    /// * Deferring the creation of an owned key until it is known to be required
+    /// * Using a search key that doesn't work with the Borrow trait
+    /// * Using custom comparison logic without newtype wrappers
+    ///
+    /// Because raw entries provide much more low-level control, it's much easier
+    /// to put the HashMap into an inconsistent state which, while memory-safe,
+    /// will cause the map to produce seemingly random results. Higher-level and
+    /// more foolproof APIs like `entry` should be preferred when possible.
+    ///
+    /// In particular, the hash used to initialized the raw entry must still be
+    /// consistent with the hash of the key that is ultimately stored in the entry.
+    /// This is because implementations of HashMap may need to recompute hashes
+    /// when resizing, at which point only the keys are available.
+    ///
+    /// Raw entries give mutable access to the keys. This must not be used
+    /// to modify how the key would compare or hash, as the map will not re-evaluate
+    /// where the key should go, meaning the keys may become "lost" if their
+    /// location does not reflect their state. For instance, if you change a key
+    /// so that the map now contains keys which compare equal, search may start
+    /// acting erratically, with two keys randomly masking each other. Implementations
+    /// are free to assume this doesn't happen (within the limits of memory-safety).
+    #[inline]
+    pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V, S> {
+        RawEntryBuilderMut { map: self }
+    }
+
+    /// Creates a raw immutable entry builder for the HashMap.
+    ///
+    /// Raw entries provide the lowest level of control for searching and
+    /// manipulating a map. They must be manually initialized with a hash and
+    /// then manually searched.
+    ///
+    /// This is useful for
+    /// * Hash memoization
+    /// * Using a search key that doesn't work with the Borrow trait
+    /// * Using custom comparison logic without newtype wrappers
+    ///
+    /// Unless you are in such a situation, higher-level and more foolproof APIs like
+    /// `get` should be preferred.
+    ///
+    /// Immutable raw entries have very limited use; you might instead want `raw_entry_mut`.
+    #[inline]
+    pub fn raw_entry(&self) -> RawEntryBuilder<'_, K, V, S> {
+        RawEntryBuilder { map: self }
+    }
+
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut letters = Map::new();
+    ///
+    /// for ch in "a short treatise on fungi".chars() {
+    ///     let counter = letters.entry(ch).or_insert(0);
+    ///     *counter += 1;
+    /// }
+    ///
+    /// assert_eq!(letters[&'s'], 2);
+    /// assert_eq!(letters[&'t'], 3);
+    /// assert_eq!(letters[&'u'], 1);
+    /// assert_eq!(letters.get(&'y'), None);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S>
+    where
+        K: Clone,
+    {
+        let hash = self.hash_key(&key);
+
+        match self.find(hash, &key) {
+            Hit(idx) => Entry::Occupied(OccupiedEntry::new(idx, key, self)),
+            Miss(parent) => {
+                let parent = parent.map(NonNull::from);
+
+                Entry::Vacant(VacantEntry::new(key, hash, parent, self))
+            }
+        }
+    }
+
+    /// Gets the corresponding entry for a borrowed key in the map for
+    /// in-place manipulation.
+    ///
+    /// Unlike [`entry`](#method.entry), this doesn't need an owned key up
+    /// front: the key is only converted with `ToOwned` when inserting into
+    /// a vacant entry, so modifying existing entries of maps with owned
+    /// keys, such as `String`s, doesn't allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ordnung::Map;
+    ///
+    /// let mut words: Map<String, u32> = Map::new();
+    ///
+    /// for word in "the cat saw the dog".split(' ') {
+    ///     *words.entry_ref(word).or_insert(0) += 1;
+    /// }
+    ///
+    /// assert_eq!(words["the"], 2);
+    /// assert_eq!(words["dog"], 1);
+    /// ```
+    pub fn entry_ref<'q, Q>(&mut self, key: &'q Q) -> EntryRef<'_, 'q, K, Q, V, S>
+    where
+        K: Borrow<Q> + Clone,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_key(key);
+
+        match self.find(hash, key) {
+            Hit(idx) => EntryRef::Occupied(OccupiedEntry::without_key(idx, self)),
+            Miss(parent) => {
+                let parent = parent.map(NonNull::from);
+
+                EntryRef::Vacant(VacantEntryRef::new(key, hash, parent, self))
+            }
+        }
+    }
+
+    /// Tries to insert a key-value pair into the map, and returns a mutable
+    /// reference to the value in the entry.
+    ///
+    /// If the map already had this key present, nothing is updated, and an
+    /// error containing the occupied entry and the value is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// assert_eq!(map.try_insert(37, "a").unwrap(), &"a");
+    ///
+    /// let err = map.try_insert(37, "b").unwrap_err();
+    /// assert_eq!(err.entry.key(), &37);
+    /// assert_eq!(err.entry.get(), &"a");
+    /// assert_eq!(err.value, "b");
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, OccupiedError<'_, K, V, S>>
+    where
+        K: Clone,
+    {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(entry) => Ok(entry.insert(value)),
+        }
+    }
+}
+
+// The `str` lookups below are deliberately not `#[inline]`: every call site
+// shares a single instance of the lookup machinery, instead of instantiating
+// it for each `&str`, `&String` or `&&str` probe type across the program.
+//
+// The `get_size` and `get_str_size` examples do the same lookups either way.
+// Built with rustc 1.95 for x86_64 Linux, at `opt-level = "s"` with a single
+// codegen unit and `panic = "abort"`, their `.text` sections came to 281699
+// and 274547 bytes, 7152 bytes (2.5%) less with `get_str`. The saving grows
+// with the number of probe types used on each map type.
+
+impl<V, S> Map<String, V, S>
+where
+    S: BuildHasher,
+{
+    /// Returns a reference to the value corresponding to the `str` key.
+    ///
+    /// This is equivalent to `get`, but is not generic over the type of the
+    /// key, which reduces code size in programs doing many such lookups.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert(String::from("a"), 1);
+    /// assert_eq!(map.get_str("a"), Some(&1));
+    /// assert_eq!(map.get_str("b"), None);
+    /// ```
+    pub fn get_str(&self, key: &str) -> Option<&V> {
+        self.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `str` key.
+    ///
+    /// This is equivalent to `get_mut`, but is not generic over the type of
+    /// the key, which reduces code size in programs doing many such lookups.
+    pub fn get_str_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+}
+
+impl<V, S> Map<&str, V, S>
+where
+    S: BuildHasher,
+{
+    /// Returns a reference to the value corresponding to the `str` key.
+    ///
+    /// This is equivalent to `get`, but is not generic over the type of the
+    /// key, which reduces code size in programs doing many such lookups.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("a", 1);
+    /// assert_eq!(map.get_str("a"), Some(&1));
+    /// assert_eq!(map.get_str("b"), None);
+    /// ```
+    pub fn get_str(&self, key: &str) -> Option<&V> {
+        self.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the `str` key.
+    ///
+    /// This is equivalent to `get_mut`, but is not generic over the type of
+    /// the key, which reduces code size in programs doing many such lookups.
+    pub fn get_str_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+}
+
+impl<K, V, S> IntoIterator for Map<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    #[inline]
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            len: self.len(),
+            inner: self.store.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a Map<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut Map<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+/// Consuming iterator
+pub struct IntoIter<K, V> {
+    inner: <Store<Node<K, V>> as IntoIterator>::IntoIter,
+    // Number of entries left, `inner` can also contain tombstones.
+    len: usize,
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(n) = self.inner.next_back() {
+            if let Some(v) = n.value {
+                self.len -= 1;
+
+                return Some((n.key, v));
+            }
+        }
+
+        None
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        for n in self.inner.by_ref() {
+            if let Some(v) = n.value {
+                self.len -= 1;
+
+                return Some((n.key, v));
+            }
+        }
+
+        None
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+/// A draining iterator over the entries of a `Map`.
+///
+/// This struct is created by the [`drain`](./struct.Map.html#method.drain)
+/// method on [`Map`](./struct.Map.html). See its documentation for more.
+pub struct Drain<'a, K, V> {
+    inner: StoreDrain<'a, Node<K, V>>,
+    len: usize,
+}
+
+impl<K, V> Drain<'_, K, V> {
+    #[inline]
+    fn take(&mut self, node: Node<K, V>) -> Option<(K, V)> {
+        match node.value {
+            Some(v) => {
+                self.len -= 1;
+
+                Some((node.key, v))
+            }
+            None => None,
+        }
+    }
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.inner.next() {
+            if let Some(entry) = self.take(node) {
+                return Some(entry);
+            }
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Drain<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.inner.next_back() {
+            if let Some(entry) = self.take(node) {
+                return Some(entry);
+            }
+        }
+
+        None
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<'_, K, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+
+/// Consuming iterator over the keys
+pub struct IntoKeys<K, V>(IntoIter<K, V>);
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Consuming iterator over the values
+pub struct IntoValues<K, V>(IntoIter<K, V>);
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, v)| v)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, Q: ?Sized, V, S> Index<&Q> for Map<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// Returns a reference to the value corresponding to the supplied key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the `Map`.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K, Q: ?Sized, V, S> IndexMut<&Q> for Map<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash,
+    S: BuildHasher,
+{
+    /// Returns a mutable reference to the value corresponding to the supplied key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the `Map`.
+    fn index_mut(&mut self, key: &Q) -> &mut V {
+        self.get_mut(key).expect("no entry found for key")
+    }
+}
+
+/// Merging with `+=` moves all entries of the right-hand map into the
+/// left-hand one, same as [`extend_from_map`]: keys present in both maps
+/// take the value from the right, while keeping their position on the left.
+///
+/// ```rust
+/// use ordnung::Map;
+///
+/// let mut totals: Map<&str, i32> = Map::new();
+///
+/// for batch in vec![vec![("a", 1), ("b", 2)], vec![("b", 3), ("c", 4)]] {
+///     totals += batch.into_iter().collect();
+/// }
+///
+/// assert!(totals.iter().eq(vec![(&"a", &1), (&"b", &3), (&"c", &4)]));
+/// ```
+///
+/// [`extend_from_map`]: ./struct.Map.html#method.extend_from_map
+impl<K, V, S> AddAssign<Map<K, V, S>> for Map<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn add_assign(&mut self, other: Map<K, V, S>) {
+        self.extend_from_map(other);
+    }
+}
+
+/// Merging with `+` produces a map with all entries of the left-hand map,
+/// followed by the new entries of the right-hand one, same as `+=`.
+///
+/// ```rust
+/// use ordnung::Map;
+///
+/// let a: Map<&str, i32> = vec![("a", 1), ("b", 2)].into_iter().collect();
+/// let b: Map<&str, i32> = vec![("b", 20), ("c", 30)].into_iter().collect();
+///
+/// let merged = a + b;
+///
+/// assert!(merged.iter().eq(vec![(&"a", &1), (&"b", &20), (&"c", &30)]));
+/// ```
+impl<K, V, S> Add for Map<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Output = Map<K, V, S>;
+
+    #[inline]
+    fn add(mut self, other: Map<K, V, S>) -> Map<K, V, S> {
+        self += other;
+        self
+    }
+}
+
+impl<IK, IV, K, V, S> FromIterator<(IK, IV)> for Map<K, V, S>
+where
+    IK: Into<K>,
+    IV: Into<V>,
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Creates a `Map` from an iterator of key-value pairs, in iteration
+    /// order, with later values overwriting earlier ones of the same key.
+    /// See [`from_iter_keep_first`](#method.from_iter_keep_first) to keep
+    /// the earlier ones instead.
+    ///
+    /// With all entries known up front, the tree is balanced once they are
+    /// all in, so that lookups don't depend on the order they arrived in.
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (IK, IV)>,
+    {
+        let iter = iter.into_iter();
+        let mut map = Map::with_capacity_and_hasher(iter.size_hint().0, S::default());
+
+        for (key, value) in iter {
+            map.insert(key.into(), value.into());
+        }
+
+        map.balance_tree();
+        map
+    }
+}
+
+impl<IK, IV, K, V, S, const N: usize> From<[(IK, IV); N]> for Map<K, V, S>
+where
+    IK: Into<K>,
+    IV: Into<V>,
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Creates a `Map` from an array of key-value pairs, in array order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let map: Map<String, u64> = Map::from([("a", 1u8), ("b", 2)]);
+    ///
+    /// assert_eq!(map["a"], 1);
+    /// assert!(map.keys().eq(&["a", "b"]));
+    /// ```
+    fn from(entries: [(IK, IV); N]) -> Self {
+        let mut map = Map::default();
+
+        map.reserve(N);
+
+        for (key, value) in entries {
+            map.insert(key.into(), value.into());
+        }
+
+        map
+    }
+}
+
+impl<K, V, S> From<Map<K, V, S>> for alloc::collections::BTreeMap<K, V>
+where
+    K: Ord,
+{
+    /// Moves all entries of the map into a `BTreeMap`, which orders them by
+    /// key instead of insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let map: Map<&str, i32> = Map::from([("b", 2), ("a", 1)]);
+    /// let sorted = BTreeMap::from(map);
+    ///
+    /// assert!(sorted.keys().eq(&["a", "b"]));
+    /// ```
+    fn from(map: Map<K, V, S>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S, T> From<Map<K, V, S>> for std::collections::HashMap<K, V, T>
+where
+    K: Hash + Eq,
+    T: BuildHasher + Default,
+{
+    /// Moves all entries of the map into a `HashMap`, which doesn't
+    /// preserve insertion order. Requires the `std` feature.
+    fn from(map: Map<K, V, S>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K, V, S> From<alloc::collections::BTreeMap<K, V>> for Map<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Moves all entries of a `BTreeMap` into a new map, inserting them in
+    /// order of their keys.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut sorted = BTreeMap::new();
+    /// sorted.insert("b", 2);
+    /// sorted.insert("a", 1);
+    ///
+    /// let map: Map<_, _> = Map::from(sorted);
+    ///
+    /// assert!(map.keys().eq(&["a", "b"]));
+    /// ```
+    fn from(map: alloc::collections::BTreeMap<K, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S, T> From<std::collections::HashMap<K, V, T>> for Map<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    /// Moves all entries of a `HashMap` into a new map, inserting them in
+    /// the arbitrary order the `HashMap` iterates in. Requires the `std`
+    /// feature.
+    fn from(map: std::collections::HashMap<K, V, T>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<IK, IV, K, V, S> Extend<(IK, IV)> for Map<K, V, S>
+where
+    IK: Into<K>,
+    IV: Into<V>,
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (IK, IV)>,
+    {
+        /// Turns automatic rebalancing back on, even if the iterator or a
+        /// conversion panics.
+        struct Guard<'a, K, V, S> {
+            map: &'a mut Map<K, V, S>,
+            auto_rebalance: bool,
+        }
+
+        impl<K, V, S> core::ops::Drop for Guard<'_, K, V, S> {
+            fn drop(&mut self) {
+                self.map.auto_rebalance = self.auto_rebalance;
+            }
+        }
+
+        let iter = iter.into_iter();
+
+        self.reserve(iter.size_hint().0);
+
+        let balance = core::mem::take(&mut self.balance_on_extend);
+
+        // Check the balance once all entries are in, rather than on
+        // every power of two along the way.
+        let auto_rebalance = core::mem::replace(&mut self.auto_rebalance, false);
+        let guard = Guard {
+            map: self,
+            auto_rebalance,
+        };
+
+        for (key, value) in iter {
+            guard.map.insert(key.into(), value.into());
+        }
+
+        drop(guard);
+
+        if balance || (auto_rebalance && !self.is_reasonably_balanced()) {
+            self.balance_tree();
+        }
+    }
+}
+
+// Because keys can inserted in different order, the safe way to
+// compare `Map`s is to iterate over one and check if the other
+// has all the same keys. Maps are usually built in the same order
+// though, so entries are first compared side by side, and only the
+// rest are looked up once they diverge. Keys are unique, so none of
+// the rest can be among the entries already matched.
+impl<K, V, S> PartialEq for Map<K, V, S>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        let mut ours = self.iter();
+        let mut theirs = other.iter();
+
+        while let Some((key, value)) = ours.next() {
+            if theirs.next() != Some((key, value)) {
+                return other.get(key) == Some(value)
+                    && ours.all(|(key, value)| other.get(key) == Some(value));
+            }
+        }
+
+        true
+    }
+}
+
+impl<K, V, S> Eq for Map<K, V, S>
+where
+    K: Hash + Eq,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+// Entries are hashed separately and summed up, so that the hash doesn't
+// depend on the order in which they were inserted. They're hashed with the
+// default hasher rather than the map's own, since maps with differently
+// seeded hashers can still be equal.
+impl<K, V, S> Hash for Map<K, V, S>
+where
+    K: Hash,
+    V: Hash,
+    S: BuildHasher,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let sum = self.iter().fold(0u64, |sum, entry| {
+            sum.wrapping_add(DefaultHashBuilder::default().hash_one(entry))
+        });
+
+        state.write_usize(self.len());
+        state.write_u64(sum);
+    }
+}
+
+/// Entries of `map` sorted by key, for anything that mustn't depend on
+/// insertion order.
+fn sorted_entries<K: Ord, V, S>(map: &Map<K, V, S>) -> alloc::vec::Vec<(&K, &V)> {
+    let mut entries: alloc::vec::Vec<_> = map.iter().collect();
+
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    entries
+}
+
+// Consistent with `PartialEq`, maps are compared as if their entries were
+// sorted by key, which takes sorting both first and makes this `O(n log n)`.
+impl<K, V, S> PartialOrd for Map<K, V, S>
+where
+    K: Ord + Hash,
+    V: PartialOrd,
+    S: BuildHasher,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        sorted_entries(self)
+            .into_iter()
+            .partial_cmp(sorted_entries(other))
+    }
+}
+
+impl<K, V, S> Ord for Map<K, V, S>
+where
+    K: Ord + Hash,
+    V: Ord,
+    S: BuildHasher,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        sorted_entries(self).into_iter().cmp(sorted_entries(other))
+    }
+}
+
+/// An iterator over the entries of a `Map`.
+///
+/// This struct is created by the [`iter`](./struct.Map.html#method.iter)
+/// method on [`Map`](./struct.Map.html). See its documentation for more.
+pub struct Iter<'a, K, V> {
+    inner: slice::Iter<'a, Node<K, V>>,
+    // Number of entries left, `inner` can also contain tombstones.
+    len: usize,
+}
+
+/// A mutable iterator over the entries of a `Map`.
+///
+/// This struct is created by the [`iter_mut`](./struct.Map.html#method.iter_mut)
+/// method on [`Map`](./struct.Map.html). See its documentation for more.
+pub struct IterMut<'a, K, V> {
+    inner: slice::IterMut<'a, Node<K, V>>,
+    // Number of entries left, `inner` can also contain tombstones.
+    len: usize,
+}
+
+impl<K, V> Iter<'_, K, V> {
+    /// Create an empty iterator that always returns `None`
+    pub fn empty() -> Self {
+        Iter {
+            inner: [].iter(),
+            len: 0,
+        }
+    }
+}
+
+impl<'i, K, V> Iterator for Iter<'i, K, V> {
+    type Item = (&'i K, &'i V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        for node in self.inner.by_ref() {
+            let value = match node.value {
+                Some(ref value) => value,
+                None => continue,
+            };
+
+            self.len -= 1;
+
+            return Some((&node.key, value));
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.inner.next_back() {
+            let value = match node.value {
+                Some(ref value) => value,
+                None => continue,
+            };
+
+            self.len -= 1;
+
+            return Some((&node.key, value));
+        }
+
+        None
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> IterMut<'_, K, V> {
+    /// Create an empty iterator that always returns `None`
+    pub fn empty() -> Self {
+        IterMut {
+            inner: [].iter_mut(),
+            len: 0,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        for node in self.inner.by_ref() {
+            let value = match node.value {
+                Some(ref mut value) => value,
+                None => continue,
+            };
+
+            self.len -= 1;
+
+            return Some((&node.key, value));
+        }
+
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.inner.next_back() {
+            let value = match node.value {
+                Some(ref mut value) => value,
+                None => continue,
+            };
+
+            self.len -= 1;
+
+            return Some((&node.key, value));
+        }
+
+        None
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    /// Walk the tree from the root, making sure every node is reachable
+    /// exactly once and sits on the correct side of all its ancestors.
+    fn check_tree<K, V, S>(map: &crate::Map<K, V, S>) {
+        let store = &map.store;
+
+        let root = match map.root_index() {
+            Some(root) => root,
+            None => return,
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        let mut stack = vec![(root, 0, u64::MAX)];
+
+        while let Some((idx, lo, hi)) = stack.pop() {
+            assert!(idx < store.len(), "Link out of bounds: {}", idx);
+            assert!(seen.insert(idx), "Node {} is linked twice", idx);
+
+            let node = &store[idx];
+
+            assert!(
+                node.hash >= lo && node.hash <= hi,
+                "Node {} out of order",
+                idx
+            );
+
+            if let Some(left) = node.left.get() {
+                assert!(node.hash > lo, "Node {} has an impossible left child", idx);
+                stack.push((left.get() as usize, lo, node.hash - 1));
+            }
+            if let Some(right) = node.right.get() {
+                stack.push((right.get() as usize, node.hash, hi));
+            }
+        }
+
+        assert_eq!(seen.len(), store.len(), "Unreachable nodes in the tree");
+    }
+
+    /// Hasher that uses integer keys as hashes verbatim, so that
+    /// sequential keys produce a degenerate tree.
+    #[derive(Default)]
+    struct IdentityHasher(u64);
+
+    impl core::hash::Hasher for IdentityHasher {
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = (self.0 << 8) | byte as u64;
+            }
+        }
+
+        fn write_u64(&mut self, n: u64) {
+            self.0 = n;
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    mod map {
+        use super::{check_tree, IdentityHasher};
+        use crate::Map;
+        use core::hash::BuildHasherDefault;
+
+        #[test]
+        fn empty() {
+            let map: Map<&str, u64> = Map::new();
+
+            assert_eq!(map.get("foo"), None);
+            assert_eq!(map.len(), 0);
+            assert!(map.is_empty());
+        }
+
+        #[test]
+        fn simple() {
+            let mut map: Map<&str, u64> = Map::new();
+
+            map.insert("foo", 42);
+
+            assert_eq!(map.get("foo"), Some(&42));
+            assert_eq!(map.len(), 1);
+            assert!(!map.is_empty());
+        }
+
+        #[test]
+        fn iter() {
+            let mut map: Map<&str, u64> = Map::new();
+
+            let elements = [("foo", 123), ("bar", 1234), ("baz", 2345), ("qux", 3456)];
+
+            for &(k, v) in &elements {
+                assert!(map.insert(k, v).is_none());
+            }
+
+            assert_eq!(map.iter().len(), elements.len());
+            assert_eq!(map.clone().into_iter().len(), elements.len());
+
+            assert_eq!(
+                &map.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+                &elements
+            );
+            assert_eq!(&map.clone().into_iter().collect::<Vec<_>>(), &elements);
+
+            let reversed_elements = elements.iter().cloned().rev().collect::<Vec<_>>();
+
+            assert_eq!(
+                &map.iter().rev().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+                &reversed_elements
+            );
+            assert_eq!(
+                &map.clone().into_iter().rev().collect::<Vec<_>>(),
+                &reversed_elements
+            );
+        }
+
+        #[test]
+        fn keys_and_values() {
+            let mut map: Map<&str, u64> = Map::new();
+
+            map.insert("a", 1);
+            map.insert("b", 2);
+            map.insert("c", 3);
+
+            assert_eq!(map.keys().len(), 3);
+            assert_eq!(map.values_mut().len(), 3);
+            assert_eq!(map.keys().rev().collect::<Vec<_>>(), &[&"c", &"b", &"a"]);
+
+            for value in map.values_mut().rev().take(2) {
+                *value *= 10;
+            }
+
+            assert_eq!(map.values().collect::<Vec<_>>(), &[&1, &20, &30]);
+            assert_eq!(crate::Keys::<u8, u8>::empty().next(), None);
+            assert_eq!(crate::ValuesMut::<u8, u8>::empty().next(), None);
+        }
+
+        #[test]
+        fn into_iter_by_reference() {
+            let mut map: Map<&str, u64> = Map::new();
+
+            map.insert("a", 1);
+            map.insert("b", 2);
+
+            for (_, value) in &mut map {
+                *value += 1;
+            }
+
+            let mut entries = Vec::new();
+
+            for (&key, &value) in &map {
+                entries.push((key, value));
+            }
+
+            assert_eq!(entries, &[("a", 2), ("b", 3)]);
+        }
+
+        #[test]
+        fn index_mut() {
+            let mut map: Map<String, u64> = Map::new();
+
+            map.insert("a".into(), 1);
+            map["a"] += 10;
+
+            assert_eq!(map["a"], 11);
+        }
+
+        #[test]
+        #[should_panic(expected = "no entry found for key")]
+        fn index_missing_key() {
+            let mut map: Map<&str, u64> = Map::new();
+
+            map["a"] = 1;
+        }
+
+        #[test]
+        fn contains_key() {
+            let mut map: Map<String, u64> = Map::new();
+
+            assert!(!map.contains_key("a"));
+
+            map.insert("a".into(), 1);
+            map.insert("b".into(), 2);
+
+            assert!(map.contains_key("a"));
+            assert!(map.contains_key(&String::from("b")));
+            assert!(!map.contains_key("c"));
+
+            map.remove("a");
+
+            assert!(!map.contains_key("a"));
+            assert!(map.contains_key("b"));
+        }
+
+        #[test]
+        fn overwrite_keeps_position() {
+            let mut map: Map<&str, u64> = Map::new();
+
+            map.insert("a", 1);
+            map.insert("b", 2);
+            map.insert("c", 3);
+
+            assert_eq!(map.insert("a", 10), Some(1));
+            assert_eq!(map.insert("b", 20), Some(2));
+
+            assert_eq!(
+                map.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+                &[("a", 10), ("b", 20), ("c", 3)]
+            );
+        }
+
+        #[test]
+        fn insertion_order_is_stable() {
+            let mut map: Map<u32, u32> = Map::new();
+
+            // Interleave fresh inserts with overwrites of earlier keys
+            for x in 0..100 {
+                map.insert(x, x);
+                map.insert(x / 2, x * 10);
+            }
+
+            assert!(map.keys().copied().eq(0..100));
+            assert_eq!(map.get(&0), Some(&10));
+            assert_eq!(map.get(&49), Some(&990));
+            assert_eq!(map.get(&99), Some(&99));
+
+            for x in (0..100).rev() {
+                *map.get_mut(&x).unwrap() += 1;
+                *map.get_or_insert(x, || unreachable!()) += 1;
+            }
+
+            assert!(map.keys().copied().eq(0..100));
+            assert_eq!(map.get(&99), Some(&101));
+        }
+
+        #[test]
+        fn get_index() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x * 10)).collect();
+
+            map.remove_lazy(&0);
+            map.remove_lazy(&4);
+
+            assert_eq!(map.get_index(0), Some((&1, &10)));
+            assert_eq!(map.get_index(3), Some((&5, &50)));
+            assert_eq!(map.get_index(7), Some((&9, &90)));
+            assert_eq!(map.get_index(8), None);
+
+            *map.get_index_mut(3).unwrap().1 += 1;
+
+            assert_eq!(map[&5], 51);
+            assert_eq!(map.get_index_mut(8), None);
+        }
+
+        #[test]
+        fn insert_full() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&2);
+
+            assert_eq!(map.insert_full(5, 50), (4, Some(5)));
+            assert_eq!(map.insert_full(2, 20), (9, None));
+            assert_eq!(map.get_index(9), Some((&2, &20)));
+
+            check_tree(&map);
+        }
+
+        #[test]
+        fn pop() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&99);
+            map.remove_lazy(&98);
+            map.remove_lazy(&50);
+
+            assert_eq!(map.pop(), Some((97, 97)));
+            assert_eq!(map.tombstone_count(), 1);
+            check_tree(&map);
+
+            map.rebalance();
+
+            for x in (51..97u32).rev() {
+                assert_eq!(map.pop(), Some((x, x)));
+                check_tree(&map);
+            }
+
+            assert_eq!(map.len(), 50);
+
+            for x in 0..50u32 {
+                assert_eq!(map.get(&x), Some(&x));
+            }
+
+            while map.pop().is_some() {}
+
+            assert!(map.is_empty());
+            assert_eq!(map.tombstone_count(), 0);
+        }
+
+        #[test]
+        fn first_and_last() {
+            let mut map: Map<u32, u32> = Map::new();
+
+            assert_eq!(map.first(), None);
+            assert_eq!(map.last(), None);
+            assert_eq!(map.first_mut(), None);
+            assert_eq!(map.last_mut(), None);
+
+            map.extend((0..10u32).map(|x| (x, x)));
+            map.remove_lazy(&0);
+            map.remove_lazy(&9);
+
+            assert_eq!(map.first(), Some((&1, &1)));
+            assert_eq!(map.last(), Some((&8, &8)));
+
+            *map.first_mut().unwrap().1 = 10;
+            *map.last_mut().unwrap().1 = 80;
+
+            assert_eq!(map[&1], 10);
+            assert_eq!(map[&8], 80);
+        }
+
+        #[test]
+        fn sort() {
+            let mut map: Map<u32, u32> = (0..100u32).rev().map(|x| (x, x % 7)).collect();
+
+            map.remove_lazy(&42);
+            map.sort_keys();
+
+            check_tree(&map);
+            assert_eq!(map.tombstone_count(), 0);
+            assert!(map.keys().copied().eq((0..100u32).filter(|&x| x != 42)));
+
+            map.sort_by(|_, v1, _, v2| v1.cmp(v2));
+
+            check_tree(&map);
+            assert!(map.values().is_sorted());
+
+            for x in 0..100u32 {
+                if x != 42 {
+                    assert_eq!(map[&x], x % 7);
+                }
+            }
+        }
+
+        #[test]
+        fn append() {
+            let mut map: Map<u32, u32> = (0..50u32).map(|x| (x, x)).collect();
+            let mut other: Map<u32, u32> = (25..75u32).map(|x| (x, x * 10)).collect();
+
+            other.remove_lazy(&30);
+
+            let capacity = other.capacity();
+
+            map.append(&mut other);
+
+            check_tree(&map);
+            assert!(other.is_empty());
+            assert_eq!(other.capacity(), capacity);
+            assert_eq!(map.len(), 75);
+            assert!(map.keys().copied().eq(0..75u32));
+            assert_eq!(map[&24], 24);
+            assert_eq!(map[&25], 250);
+            assert_eq!(map[&30], 30);
+
+            let more: Map<u32, u32> = (70..80u32).map(|x| (x, 0u32)).collect();
+
+            map.extend_from_map(more);
+
+            check_tree(&map);
+            assert_eq!(map.len(), 80);
+            assert_eq!(map[&69], 690);
+            assert_eq!(map[&70], 0);
+        }
+
+        #[test]
+        #[should_panic(expected = "exceeds maximum of u32::MAX entries")]
+        fn link_out_of_bounds() {
+            // Filling a map up for real would take way too much memory
+            let _ = crate::link_to(u32::MAX as usize);
+        }
+
+        #[test]
+        fn map_values() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&50);
+
+            let map = map.map_values(|key, value| (key + value) as u64);
+
+            check_tree(&map);
+            assert_eq!(map.len(), 99);
+            assert_eq!(map.tombstone_count(), 1);
+            assert_eq!(map.get(&50), None);
+
+            for x in (0..100u32).filter(|&x| x != 50) {
+                assert_eq!(map[&x], x as u64 * 2);
+            }
+        }
+
+        #[test]
+        fn clone_from() {
+            let mut map: Map<u32, String> = (0..100u32).map(|x| (x, x.to_string())).collect();
+            let source: Map<u32, String> = (0..50u32).map(|x| (x * 2, x.to_string())).collect();
+
+            let capacity = map.capacity();
+
+            map.clone_from(&source);
+
+            check_tree(&map);
+            assert_eq!(map, source);
+            assert_eq!(map.capacity(), capacity);
+
+            let mut small: Map<u32, String> = Map::new();
+
+            small.clone_from(&map);
+
+            check_tree(&small);
+            assert_eq!(small, source);
+        }
+
+        #[test]
+        fn iter_tree() {
+            let mut map: Map<u32, u32> = (0..1000u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&500);
+
+            let hashes: Vec<u64> = map.iter_tree().map(|(key, _)| map.hash_key(key)).collect();
+
+            assert_eq!(hashes.len(), 999);
+            assert!(hashes.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(map
+                .iter_tree()
+                .all(|(key, value)| key == value && *key != 500));
+
+            map.balance_tree();
+
+            assert!(map
+                .iter_tree()
+                .map(|(key, _)| map.hash_key(key))
+                .eq(hashes.iter().copied()));
+        }
+
+        #[test]
+        fn depth_stats() {
+            let mut map: Map<u64, u64, BuildHasherDefault<IdentityHasher>> = Map::default();
+
+            assert_eq!(map.average_depth(), 0.0);
+
+            for x in 0..100 {
+                map.insert(x, x);
+            }
+
+            assert_eq!(map.max_depth(), 100);
+            assert_eq!(map.average_depth(), 50.5);
+
+            map.rebalance();
+
+            // The root is stuck with the lowest hash, atop a balanced tree
+            assert_eq!(map.max_depth(), 8);
+            assert!(map.average_depth() < 7.0);
+        }
+
+        #[test]
+        fn validate() {
+            use crate::IntegrityError::*;
+            use core::num::NonZeroU32;
+
+            let chain = || -> Map<u64, u64, BuildHasherDefault<IdentityHasher>> {
+                (0..10u64).map(|x| (x, x)).collect()
+            };
+            let link = |idx| NonZeroU32::new(idx);
+
+            let map = chain();
+            assert_eq!(map.validate(), Ok(()));
+
+            map.store[9].right.set(link(100));
+            assert_eq!(map.validate(), Err(LinkOutOfBounds { index: 9 }));
+
+            let map = chain();
+            map.store[9].right.set(link(5));
+            assert_eq!(map.validate(), Err(LinkedTwice { index: 5 }));
+
+            let map = chain();
+            map.store[3].right.set(None);
+            assert_eq!(map.validate(), Err(Unreachable { index: 4 }));
+
+            let map = chain();
+            map.store[2].left.set(map.store[3].right.take());
+            assert_eq!(map.validate(), Err(OutOfOrder { index: 4 }));
+        }
+
+        #[test]
+        fn get_full() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&2);
+
+            assert_eq!(map.get_full(&2), None);
+            assert_eq!(map.get_full(&5), Some((4, &5, &5)));
+
+            let (idx, _, value) = map.get_full_mut(&9).unwrap();
+
+            *value = 90;
+
+            assert_eq!(map.get_index(idx), Some((&9, &90)));
+            assert_eq!(map.get_full_mut(&2), None);
+        }
+
+        #[test]
+        fn reorder_indices() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+            map.swap_indices(0, 8);
+
+            check_tree(&map);
+            assert!(map.keys().copied().eq(vec![9, 1, 2, 4, 5, 6, 7, 8, 0]));
+
+            map.move_index(0, 4);
+
+            check_tree(&map);
+            assert!(map.keys().copied().eq(vec![1, 2, 4, 5, 9, 6, 7, 8, 0]));
+
+            map.move_index(8, 0);
+
+            check_tree(&map);
+            assert!(map.keys().copied().eq(vec![0, 1, 2, 4, 5, 9, 6, 7, 8]));
+            assert!((0..10u32).all(|x| x == 3 || map[&x] == x));
+        }
+
+        #[test]
+        #[should_panic(expected = "out of bounds")]
+        fn move_index_out_of_bounds() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+            map.move_index(0, 9);
+        }
+
+        #[test]
+        fn reverse() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&50);
+            map.reverse();
+
+            check_tree(&map);
+            assert!(map
+                .keys()
+                .copied()
+                .eq((0..100u32).rev().filter(|&x| x != 50)));
+            assert!((0..100u32).all(|x| x == 50 || map[&x] == x));
+            assert_eq!(map.get(&50), None);
+        }
+
+        #[test]
+        fn split_off() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&10);
+            map.remove_lazy(&90);
+
+            let tail = map.split_off(49);
+
+            check_tree(&map);
+            check_tree(&tail);
+            assert_eq!(map.len(), 49);
+            assert_eq!(tail.len(), 49);
+            assert_eq!(map.tombstone_count() + tail.tombstone_count(), 2);
+            assert!(map.keys().copied().eq((0..50u32).filter(|&x| x != 10)));
+            assert!(tail.keys().copied().eq((50..100u32).filter(|&x| x != 90)));
+            assert!(tail
+                .iter()
+                .all(|(key, value)| map.get(key).is_none() && key == value));
+
+            let empty = map.split_off(49);
+
+            assert!(empty.is_empty());
+            assert_eq!(map.len(), 49);
+        }
+
+        #[test]
+        fn debug() {
+            let mut map = Map::new();
+
+            map.insert("b", 2);
+            map.insert("a", 1);
+            map.insert("c", 3);
+            map.remove_lazy("a");
+
+            assert_eq!(format!("{:?}", map), r#"{"b": 2, "c": 3}"#);
+            assert_eq!(format!("{:?}", Map::<u8, u8>::new()), "{}");
+        }
+
+        #[test]
+        fn partition() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&10);
+
+            let (small, large) = map.partition(|&key, _| key < 30);
+
+            check_tree(&small);
+            check_tree(&large);
+            assert_eq!(small.tombstone_count() + large.tombstone_count(), 0);
+            assert!(small.keys().copied().eq((0..30u32).filter(|&x| x != 10)));
+            assert!(large.keys().copied().eq(30..100u32));
+        }
+
+        #[test]
+        fn with_hasher_from() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&10);
+
+            let other: Map<u32, u32, BuildHasherDefault<IdentityHasher>> =
+                Map::with_hasher_from(&map, Default::default());
+
+            check_tree(&other);
+            assert_eq!(other.tombstone_count(), 0);
+            assert!(other.iter().eq(map.iter()));
+            assert!(map.keys().all(|key| other.get(key) == Some(key)));
+        }
+
+        #[test]
+        fn get_or_insert_ref() {
+            let mut map: Map<String, usize> = Map::new();
+
+            for (i, word) in "a b c a b a".split(' ').enumerate() {
+                *map.get_or_insert_ref(word, || i) += 10;
+            }
+
+            check_tree(&map);
+            assert!(map.keys().eq(&["a", "b", "c"]));
+            assert!(map.values().eq(&[30, 21, 12]));
+        }
+
+        #[test]
+        fn truncate() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&10);
+            map.remove_lazy(&60);
+            map.truncate(50);
+
+            check_tree(&map);
+            assert_eq!(map.len(), 50);
+            assert_eq!(map.tombstone_count(), 1);
+            assert!(map.keys().copied().eq((0..51u32).filter(|&x| x != 10)));
+            assert!((51..100u32).all(|x| map.get(&x).is_none()));
+
+            map.truncate(50);
+            assert_eq!(map.len(), 50);
+
+            map.balance_tree();
+            map.truncate(20);
+
+            check_tree(&map);
+            assert!(map.keys().copied().eq((0..21u32).filter(|&x| x != 10)));
+            assert!(map.iter().all(|(key, value)| map.get(key) == Some(value)));
+        }
+
+        #[test]
+        fn remove_all() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&1);
+
+            let odd: Vec<u32> = (0..100u32).filter(|x| x % 2 == 1).collect();
+
+            assert_eq!(map.remove_all(&odd), 49);
+            assert_eq!(map.remove_all(&odd), 0);
+
+            check_tree(&map);
+            assert_eq!(map.tombstone_count(), 0);
+            assert!(map.keys().copied().eq((0..100u32).step_by(2)));
+        }
+
+        #[test]
+        fn try_insert() {
+            let mut map = Map::new();
+
+            *map.try_insert("a", 1).unwrap() += 10;
+
+            let err = map.try_insert("a", 2).unwrap_err();
+
+            assert_eq!(err.value, 2);
+            assert_eq!(
+                format!("{}", err),
+                r#"failed to insert 2, key "a" already exists with value 11"#
+            );
+            assert_eq!(map["a"], 11);
+            assert_eq!(map.len(), 1);
+        }
+
+        #[test]
+        fn first_and_last_entry() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&0);
+            map.remove_lazy(&9);
+
+            assert_eq!(map.first_entry().unwrap().key(), &1);
+            assert_eq!(map.last_entry().unwrap().key(), &8);
+
+            *map.first_entry().unwrap().get_mut() = 100;
+            assert_eq!(map[&1], 100);
+
+            while let Some(entry) = map.last_entry() {
+                let key = *entry.key();
+
+                assert_eq!(entry.remove_entry().0, key);
+                check_tree(&map);
+            }
+
+            assert!(map.is_empty());
+            assert!(map.first_entry().is_none());
+        }
+
+        #[test]
+        fn iterators_are_exact_and_fused() {
+            fn check<I: core::iter::FusedIterator>(mut iter: I, len: usize) {
+                assert_eq!(iter.size_hint(), (len, Some(len)));
+                assert_eq!(iter.by_ref().count(), len);
+                assert_eq!(iter.size_hint(), (0, Some(0)));
+                assert!(iter.next().is_none());
+                assert!(iter.next().is_none());
+            }
+
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            check(map.iter(), 9);
+            check(map.iter_mut(), 9);
+            check(map.keys(), 9);
+            check(map.values(), 9);
+            check(map.values_mut(), 9);
+            check(map.clone().into_iter(), 9);
+            check(map.clone().into_keys(), 9);
+            check(map.clone().into_values(), 9);
+            check(map.drain(), 9);
+        }
+
+        #[test]
+        fn from_iter_filtered() {
+            let map: Map<u32, u32> =
+                Map::from_iter_filtered((0..100u32).map(|x| (x % 50, x)), |key, _| key % 2 == 0);
+
+            check_tree(&map);
+            assert_eq!(map.len(), 25);
+            assert!(map.keys().copied().eq((0..50u32).step_by(2)));
+            assert!(map.iter().all(|(key, value)| *value == key + 50));
+        }
+
+        #[test]
+        fn get_or_try_insert_with() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            for x in 0..20u32 {
+                let result = map.get_or_try_insert_with(x, || match x % 2 {
+                    0 => Ok(x * 10),
+                    _ => Err(x),
+                });
+
+                match x {
+                    0..=9 => assert_eq!(result.copied(), Ok(x)),
+                    _ if x % 2 == 0 => assert_eq!(result.copied(), Ok(x * 10)),
+                    _ => assert_eq!(result.copied(), Err(x)),
+                }
+
+                check_tree(&map);
+            }
+
+            assert_eq!(map.len(), 15);
+            assert!(map
+                .keys()
+                .copied()
+                .eq((0..10u32).chain((10..20).step_by(2))));
+        }
+
+        #[test]
+        fn locate() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            for (index, key) in map.keys().enumerate() {
+                let hash = map.hash_key(key);
+
+                assert_eq!(map.locate(hash, |stored| stored == key), Some(index));
+                assert_eq!(map.locate(hash, |_| false), None);
+            }
+
+            assert_eq!(map.locate(map.hash_key(3u32), |_| true), None);
+        }
+
+        #[test]
+        fn get_or_insert_full() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            assert_eq!(map.get_or_insert_full(5, || 0), (4, &mut 5));
+            assert_eq!(map.get_or_insert_full(3, || 30), (9, &mut 30));
+
+            check_tree(&map);
+            assert_eq!(map.get_index(9), Some((&3, &30)));
+        }
+
+        #[test]
+        fn insert_or_merge() {
+            let mut map: Map<u32, Vec<u32>> = Map::new();
+
+            map.extend_merging((0..100u32).map(|x| (x % 10, vec![x])), |all, mut new| {
+                all.append(&mut new)
+            });
+            map.insert_or_merge(10u32, vec![100], |_, _| unreachable!());
+
+            check_tree(&map);
+            assert_eq!(map.len(), 11);
+            assert!(map.keys().copied().eq(0..11u32));
+            assert!((0..10u32).all(|x| map[&x].iter().copied().eq((x..100).step_by(10))));
+            assert_eq!(map[&10], [100]);
+        }
+
+        #[test]
+        fn stored_hashes() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            assert_eq!(map.get_hash(&3), None);
+            assert_eq!(map.hash_at(9), None);
+
+            for (index, key) in map.keys().enumerate() {
+                assert_eq!(map.get_hash(key), Some(map.hash_key(key)));
+                assert_eq!(map.hash_at(index), Some(map.hash_key(key)));
+            }
+        }
+
+        #[test]
+        fn from_iter_keep_first() {
+            let map: Map<u32, u32> = Map::from_iter_keep_first((0..100u32).map(|x| (x % 30, x)));
+
+            check_tree(&map);
+            assert_eq!(map.len(), 30);
+            assert!(map.iter().all(|(key, value)| key == value));
+        }
+
+        #[test]
+        fn try_from_iter() {
+            let map: Map<u32, u32> = Map::try_from_iter((0..100u32).map(|x| (x, x))).unwrap();
+
+            check_tree(&map);
+            assert!(map.keys().copied().eq(0..100u32));
+
+            let err = Map::<u32, u32>::try_from_iter((0..100u32).map(|x| (x % 60, x))).unwrap_err();
+
+            assert_eq!(err.key, 0);
+        }
+
+        #[test]
+        fn from_sorted_iter() {
+            let map: Map<u32, u32> = Map::from_sorted_iter((0..2000u32).map(|x| (x / 2, x)));
+
+            check_tree(&map);
+            assert_eq!(map.len(), 1000);
+            assert!(map.keys().copied().eq(0..1000u32));
+            assert!((0..1000u32).all(|x| map[&x] == x * 2 + 1));
+            assert!(map.is_balanced(1.0));
+        }
+
+        #[test]
+        #[should_panic(expected = "not sorted")]
+        fn from_sorted_iter_unsorted() {
+            let _: Map<u32, u32> = Map::from_sorted_iter(vec![(1, 1), (3, 3), (2, 2)]);
+        }
+
+        #[test]
+        fn get_index_unchecked() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            for index in 0..map.len() {
+                let expected = map.get_index(index).map(|(&k, &v)| (k, v));
+                let (&key, value) = unsafe { map.get_index_unchecked_mut(index) };
+
+                *value += 100;
+
+                assert_eq!(Some((key, *value - 100)), expected);
+                assert_eq!(
+                    unsafe { map.get_index_unchecked(index) },
+                    (&key, &(key + 100))
+                );
+            }
+        }
+
+        #[test]
+        fn retain_indexed() {
+            let mut map: Map<u32, u32> = (0..20u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            let mut seen = Vec::new();
+
+            map.retain_indexed(|index, &key, _| {
+                seen.push((index, key));
+                index % 2 == 0
+            });
+
+            check_tree(&map);
+            assert!(seen
+                .iter()
+                .map(|&(_, key)| key)
+                .eq((0..20u32).filter(|&x| x != 3)));
+            assert!(seen.iter().enumerate().all(|(i, &(index, _))| i == index));
+            assert!(map.keys().eq(&[0, 2, 5, 7, 9, 11, 13, 15, 17, 19]));
+        }
+
+        #[test]
+        fn canonicalize() {
+            let mut a: Map<u32, u32> = (0..50u32).map(|x| (x, x)).collect();
+            let mut b: Map<u32, u32> = (0..60u32).rev().map(|x| (x, x)).collect();
+
+            for x in 50..60u32 {
+                b.remove_lazy(&x);
+            }
+
+            a.canonicalize();
+            b.canonicalize();
+
+            check_tree(&a);
+            check_tree(&b);
+            assert_eq!(b.tombstone_count(), 0);
+            assert!(a.iter().eq(b.iter()));
+            assert!(a.store == b.store);
+        }
+
+        #[test]
+        fn update_keys() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&7);
+            map.update_keys(|key| *key /= 2);
+
+            check_tree(&map);
+            assert_eq!(map.tombstone_count(), 0);
+            assert!(map.keys().copied().eq(0..50u32));
+            assert_eq!(map[&3], 6);
+            assert!((0..50u32).filter(|&x| x != 3).all(|x| map[&x] == x * 2 + 1));
+        }
+
+        #[test]
+        fn insert_at() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x * 2, x)).collect();
+
+            map.remove_lazy(&4);
+
+            assert_eq!(map.insert_at(2, 3, 100), None);
+            assert_eq!(map.insert_at(0, 6, 300), Some(3));
+            assert_eq!(map.insert_at(10, 19, 200), None);
+
+            check_tree(&map);
+            assert!(map.keys().eq(&[0, 2, 3, 6, 8, 10, 12, 14, 16, 18, 19]));
+            assert_eq!(map[&6], 300);
+            assert!(map.iter().all(|(key, value)| map.get(key) == Some(value)));
+        }
+
+        #[test]
+        #[should_panic(expected = "insertion index out of bounds")]
+        fn insert_at_out_of_bounds() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.insert_at(11, 11, 11);
+        }
+
+        #[test]
+        fn auto_rebalance() {
+            /// Hasher producing hashes in insertion order for sequential
+            /// keys, the worst case for the tree.
+            #[derive(Default)]
+            struct SequentialHasher(u64);
+
+            impl core::hash::Hasher for SequentialHasher {
+                fn write(&mut self, _: &[u8]) {
+                    unimplemented!()
+                }
+
+                fn write_u32(&mut self, n: u32) {
+                    self.0 = n as u64;
+                }
+
+                fn finish(&self) -> u64 {
+                    self.0
+                }
+            }
+
+            let mut map: Map<u32, u32, BuildHasherDefault<SequentialHasher>> = Map::default();
+
+            map.set_auto_rebalance(true);
+
+            for x in 0..1000u32 {
+                map.insert(x, x);
+            }
+
+            // Balanced at 512 entries, the rest hangs off the right
+            assert_eq!(map.max_depth(), 10 + 1000 - 512);
+
+            for x in 1000..1024u32 {
+                map.insert(x, x);
+            }
+
+            check_tree(&map);
+            assert!(map.is_reasonably_balanced());
+            assert!((0..1024u32).all(|x| map[&x] == x));
+
+            let mut extended: Map<u32, u32, BuildHasherDefault<SequentialHasher>> = Map::default();
+
+            extended.set_auto_rebalance(true);
+            extended.extend((0..1000u32).map(|x| (x, x)));
+
+            check_tree(&extended);
+            assert!(extended.is_reasonably_balanced());
+            assert!(extended.auto_rebalance());
+
+            let mut clone = map.clone();
+
+            assert!(clone.auto_rebalance());
+
+            clone.set_auto_rebalance(false);
+
+            for x in 1024..1124u32 {
+                clone.insert(x, x);
+            }
+
+            assert_eq!(clone.max_depth(), map.max_depth() + 100);
+
+            // Every other way of appending entries rebalances the same
+            let mut entries: Map<u32, u32, BuildHasherDefault<SequentialHasher>> = Map::default();
+            let mut filled: Map<u32, u32, BuildHasherDefault<SequentialHasher>> = Map::default();
+            let mut merged: Map<u32, u32, BuildHasherDefault<SequentialHasher>> = Map::default();
+
+            entries.set_auto_rebalance(true);
+            filled.set_auto_rebalance(true);
+            merged.set_auto_rebalance(true);
+
+            for x in 0..1000u32 {
+                *entries.entry(x).or_insert(0) += x;
+                filled.get_or_insert(x, || x);
+            }
+
+            merged
+                .merge(map.clone(), crate::MergePolicy::KeepExisting)
+                .unwrap();
+
+            for other in &[&entries, &filled] {
+                check_tree(other);
+                assert_eq!(other.max_depth(), 10 + 1000 - 512);
+            }
+
+            check_tree(&merged);
+            assert!(merged.is_reasonably_balanced());
+        }
+
+        #[test]
+        fn with_capacity_balanced() {
+            let mut map: Map<u32, u32> = Map::with_capacity_balanced(1000);
+
+            map.extend((0..1000u32).map(|x| (x, x)));
+
+            check_tree(&map);
+            assert!(map.is_balanced(1.0));
+            assert!(!map.auto_rebalance());
+            assert!(!map.balance_on_extend);
+
+            let mut clone = Map::<u32, u32>::with_capacity_balanced(10).clone();
+
+            clone.extend((0..1000u32).map(|x| (x, x)));
+
+            assert!(clone.is_balanced(1.0));
+        }
+
+        #[test]
+        fn extend_panicking_keeps_auto_rebalance() {
+            let mut map: Map<u32, u32> = Map::new();
+
+            map.set_auto_rebalance(true);
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                map.extend((0..10u32).map(|x| {
+                    assert!(x < 5);
+                    (x, x)
+                }))
+            }));
+
+            assert!(result.is_err());
+            assert!(map.auto_rebalance());
+            assert_eq!(map.len(), 5);
+        }
+
+        #[test]
+        fn insert_borrowed() {
+            let mut map: Map<String, usize> = Map::new();
+
+            for (i, word) in "a b c a b a".split(' ').enumerate() {
+                map.insert_borrowed(word, i);
+            }
+
+            check_tree(&map);
+            assert!(map.keys().eq(&["a", "b", "c"]));
+            assert!(map.values().eq(&[5, 4, 2]));
+        }
+
+        #[test]
+        fn collect_balances_tree() {
+            let map: Map<u64, u64, BuildHasherDefault<IdentityHasher>> =
+                (0..1000u64).rev().map(|x| (x, x)).collect();
+
+            check_tree(&map);
+            assert!(map.keys().copied().eq((0..1000u64).rev()));
+            assert_eq!(map.max_depth(), 11);
+            assert!((0..1000u64).all(|x| map[&x] == x));
+        }
+
+        #[test]
+        fn get_disjoint_mut() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            let [a, b, c, d] = map.get_disjoint_mut([&1, &8, &3, &20]);
+
+            assert_eq!(c, None);
+            assert_eq!(d, None);
+
+            core::mem::swap(a.unwrap(), b.unwrap());
+
+            assert_eq!(map[&1], 8);
+            assert_eq!(map[&8], 1);
+        }
+
+        #[test]
+        #[should_panic(expected = "duplicate keys")]
+        fn get_disjoint_mut_overlapping() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            let _ = map.get_disjoint_mut([&1, &2, &1]);
+        }
+
+        #[test]
+        fn get_many() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x * 10)).collect();
+
+            map.remove_lazy(&3);
+
+            assert_eq!(
+                map.get_many([&1, &3, &20, &9, &1]),
+                [Some(&10), None, None, Some(&90), Some(&10)]
+            );
+            assert_eq!(map.get_many::<u32, 0>([]), []);
+        }
+
+        #[test]
+        fn get2_mut() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            assert_eq!(map.get2_mut(&1, &3), None);
+            assert_eq!(map.get2_mut(&20, &1), None);
+            assert_eq!(map.get2_mut(&1, &1), None);
+
+            let (a, b) = map.get2_mut(&1, &8).unwrap();
+
+            *a += 100;
+            *b += 200;
+
+            assert_eq!(map[&1], 101);
+            assert_eq!(map[&8], 208);
+        }
+
+        #[test]
+        fn with_hasher() {
+            use crate::MergePolicy;
+            use std::collections::hash_map::RandomState;
+
+            let mut map = Map::with_hasher(RandomState::new());
+            let mut other = Map::with_capacity_and_hasher(8, RandomState::new());
+
+            for x in 0..100u32 {
+                map.insert(x, x);
+                other.insert(x + 50, x + 50);
+            }
+
+            map.merge(other, MergePolicy::KeepExisting).unwrap();
+
+            check_tree(&map);
+            assert_eq!(map.len(), 150);
+
+            for x in 0..150u32 {
+                assert_eq!(map.get(&x), Some(&x));
+            }
+        }
+
+        #[test]
+        fn colliding_hashes() {
+            use core::hash::Hasher;
+
+            /// Hasher squashing all keys into 4 distinct hashes.
+            #[derive(Default)]
+            struct CollidingHasher(u64);
+
+            impl Hasher for CollidingHasher {
+                fn write(&mut self, bytes: &[u8]) {
+                    for &byte in bytes {
+                        self.0 = self.0.wrapping_add(byte as u64);
+                    }
+                }
+
+                fn finish(&self) -> u64 {
+                    self.0 % 4
+                }
+            }
+
+            let mut map: Map<u32, u32, BuildHasherDefault<CollidingHasher>> = Map::default();
+
+            for x in 0..64 {
+                assert_eq!(map.insert(x, x), None);
+            }
+            for x in 0..64 {
+                assert_eq!(map.insert(x, x * 2), Some(x));
+            }
+
+            check_tree(&map);
+            assert_eq!(map.len(), 64);
+            assert!((0..64).all(|x| map.get(&x) == Some(&(x * 2))));
+            assert_eq!(map.get(&64), None);
+
+            for x in (0..64).step_by(3) {
+                assert_eq!(map.remove(&x), Some(x * 2));
+            }
+
+            check_tree(&map);
+            assert!((0..64).all(|x| map.contains_key(&x) == (x % 3 != 0)));
+        }
+
+        #[test]
+        fn single_colliding_hash() {
+            /// Hasher squashing all keys into the same hash.
+            #[derive(Default)]
+            struct ConstHasher;
+
+            impl core::hash::Hasher for ConstHasher {
+                fn write(&mut self, _: &[u8]) {}
+
+                fn finish(&self) -> u64 {
+                    42
+                }
+            }
+
+            let mut map: Map<u32, u32, BuildHasherDefault<ConstHasher>> = Map::default();
+
+            for x in 0..200 {
+                map.insert(x, x);
+            }
+
+            // Every key collides, so the tree is a single chain to the right
+            check_tree(&map);
+            assert_eq!(map.max_depth(), 200);
+
+            for x in (0..200).step_by(2) {
+                assert_eq!(map.remove_lazy(&x), Some(x));
+            }
+            for x in (0..200).step_by(4) {
+                assert_eq!(map.insert(x, x + 1000), None);
+            }
+
+            check_tree(&map);
+            assert!((0..200).all(|x| match x % 4 {
+                0 => map.get(&x) == Some(&(x + 1000)),
+                2 => map.get(&x).is_none(),
+                _ => map.get(&x) == Some(&x),
+            }));
+
+            map.balance_tree();
+
+            check_tree(&map);
+            assert!(map.iter().all(|(key, value)| map.get(key) == Some(value)));
+
+            map.remove(&1);
+
+            check_tree(&map);
+            assert_eq!(map.len(), 149);
+            assert_eq!(map.get(&1), None);
+            assert!(map.iter().all(|(key, value)| map.get(key) == Some(value)));
+        }
+
+        #[test]
+        fn entry_links_vacant_inserts() {
+            let mut map: Map<u32, u32> = Map::new();
+
+            for x in 0..100 {
+                *map.entry(x % 40).or_default() += 1;
+                map.entry(x % 30).and_modify(|v| *v += 10).or_insert(0);
+            }
+
+            check_tree(&map);
+            assert_eq!(map.len(), 40);
+            assert!(map.keys().copied().eq(0..40));
+            assert_eq!(map[&0], 3 + 40);
+            assert_eq!(map[&35], 2);
+        }
+
+        #[test]
+        fn entry_ref_converts_only_vacant_keys() {
+            use core::borrow::Borrow;
+            use core::cell::Cell;
+
+            thread_local! {
+                static CONVERSIONS: Cell<usize> = const { Cell::new(0) };
+            }
+
+            /// Borrowed key counting its conversions into an owned `Key`.
+            #[derive(Hash, PartialEq, Eq)]
+            #[repr(transparent)]
+            struct Probe(u32);
+
+            #[derive(Clone, Hash, PartialEq, Eq)]
+            #[repr(transparent)]
+            struct Key(u32);
+
+            impl Borrow<Probe> for Key {
+                fn borrow(&self) -> &Probe {
+                    // Both are transparent wrappers of the same `u32`
+                    unsafe { &*(self as *const Key as *const Probe) }
+                }
+            }
+
+            impl ToOwned for Probe {
+                type Owned = Key;
+
+                fn to_owned(&self) -> Key {
+                    CONVERSIONS.with(|count| count.set(count.get() + 1));
+                    Key(self.0)
+                }
+            }
+
+            let mut map: Map<Key, u32> = Map::new();
+
+            for x in 0..100 {
+                map.entry_ref(&Probe(x % 40))
+                    .and_modify(|v| *v += 1)
+                    .or_insert(0);
+            }
+
+            check_tree(&map);
+            assert_eq!(CONVERSIONS.with(Cell::get), 40);
+            assert_eq!(map.len(), 40);
+            assert_eq!(map[&Probe(0)], 2);
+            assert_eq!(map[&Probe(39)], 1);
+        }
+
+        #[test]
+        fn extend() {
+            let mut map: Map<String, u64> = Map::new();
+
+            map.insert("a".into(), 1);
+            map.extend(vec![("b", 2u8), ("a", 10)]);
+            map.extend(Some((String::from("c"), 3u64)));
+
+            check_tree(&map);
+            assert!(map.keys().eq(&["a", "b", "c"]));
+            assert!(map.values().eq(&[10, 2, 3]));
+        }
+
+        #[test]
+        fn hash_ignores_insertion_order() {
+            use core::hash::{BuildHasher, Hash, Hasher};
+            use std::collections::hash_map::{DefaultHasher, RandomState};
+
+            fn hash<S: BuildHasher>(map: &Map<&str, i32, S>) -> u64 {
+                let mut hasher = DefaultHasher::new();
+
+                map.hash(&mut hasher);
+                hasher.finish()
+            }
+
+            let a: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+            let b: Map<&str, i32> = vec![("c", 3), ("a", 1), ("b", 2)].into_iter().collect();
+            let c: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 4)].into_iter().collect();
+
+            assert_eq!(hash(&a), hash(&b));
+            assert_ne!(hash(&a), hash(&c));
+            assert_ne!(hash(&a), hash(&Map::new()));
+
+            // Equal maps hash the same, even with differently seeded hashers
+            let mut d = Map::with_hasher(RandomState::new());
+            let mut e = Map::with_hasher(RandomState::new());
+
+            for (&key, &value) in a.iter() {
+                d.insert(key, value);
+            }
+            for (&key, &value) in b.iter() {
+                e.insert(key, value);
+            }
+
+            assert_eq!(d, e);
+            assert_eq!(hash(&a), hash(&d));
+            assert_eq!(hash(&d), hash(&e));
+        }
+
+        #[test]
+        fn entries_are_send_and_sync() {
+            use crate::{
+                DefaultHashBuilder, OccupiedEntry, RawOccupiedEntryMut, VacantEntry, VacantEntryRef,
+            };
+
+            fn check<T: Send + Sync>() {}
+
+            check::<OccupiedEntry<'_, String, u32, DefaultHashBuilder>>();
+            check::<VacantEntry<'_, String, u32, DefaultHashBuilder>>();
+            check::<RawOccupiedEntryMut<'_, String, u32, DefaultHashBuilder>>();
+            check::<VacantEntryRef<'_, '_, String, str, u32, DefaultHashBuilder>>();
+        }
+
+        #[test]
+        fn equality_ignores_insertion_order() {
+            let a: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+            let b: Map<&str, i32> = vec![("c", 3), ("a", 1), ("b", 2)].into_iter().collect();
+            let c: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 4)].into_iter().collect();
+
+            assert_eq!(a, b);
+            assert_ne!(a, c);
+            assert!(!a.eq_ordered(&b));
+            assert!(a.eq_ordered(&a.clone()));
+        }
+
+        #[test]
+        // The links between nodes are cells, but they don't affect ordering
+        #[allow(clippy::mutable_key_type)]
+        fn ordering_ignores_insertion_order() {
+            use core::cmp::Ordering;
+            use std::collections::BTreeMap;
+
+            let a: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+            let b: Map<&str, i32> = vec![("c", 3), ("a", 1), ("b", 2)].into_iter().collect();
+            let c: Map<&str, i32> = vec![("a", 1), ("b", 2), ("c", 4)].into_iter().collect();
+            let d: Map<&str, i32> = vec![("b", 0)].into_iter().collect();
+
+            assert_eq!(a.cmp(&b), Ordering::Equal);
+            assert!(a < c);
+            assert!(c < d);
+            assert!(Map::new() < a);
+
+            let mut sorted = vec![d.clone(), c.clone(), b.clone(), Map::new(), a.clone()];
+
+            sorted.sort();
+
+            assert_eq!(
+                sorted,
+                [Map::new(), a.clone(), b.clone(), c.clone(), d.clone()]
+            );
+
+            let mut keys = BTreeMap::new();
+
+            keys.insert(d, "d");
+            keys.insert(c, "c");
+            keys.insert(a, "a");
+
+            assert_eq!(keys.insert(b, "b"), Some("a"));
+            assert!(keys.values().eq(&["b", "c", "d"]));
+        }
+
+        #[test]
+        fn equality_after_common_prefix() {
+            let a: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+            let mut b = a.clone();
+            let mut c = a.clone();
+
+            b.swap_indices(40, 60);
+            c.swap_indices(40, 60);
+            c[&99] = 0;
+
+            assert_eq!(a, a.clone());
+            assert_eq!(a, b);
+            assert_eq!(b, a);
+            assert_ne!(a, c);
+            assert_ne!(c, a);
+        }
+
+        #[test]
+        fn map_macro() {
+            let map: Map<&str, i32> = crate::map! { "a" => 1, "b" => 2, "a" => 3 };
+
+            check_tree(&map);
+            assert_eq!(map.len(), 2);
+            assert_eq!(map.capacity(), 3);
+            assert!(map.iter().eq(vec![(&"a", &3), (&"b", &2)]));
+        }
+
+        #[test]
+        fn evict_oldest() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+            map.remove_lazy(&50);
+
+            assert!(map
+                .evict_oldest(10)
+                .eq((0..11u32).filter(|&x| x != 3).map(|x| (x, x))));
+
+            check_tree(&map);
+            assert_eq!(map.len(), 88);
+            assert_eq!(map.tombstone_count(), 1);
+            assert!(map.keys().copied().eq((11..100u32).filter(|&x| x != 50)));
+
+            assert_eq!(map.evict_oldest(1000).count(), 88);
+            assert!(map.is_empty());
+            assert_eq!(map.tombstone_count(), 0);
+        }
+
+        #[test]
+        fn range() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            assert!(map.range(..).eq(map.iter()));
+            assert!(map.range(2..5).map(|(k, _)| *k).eq([2, 4, 5]));
+            assert!(map.range(2..=4).rev().map(|(k, _)| *k).eq([5, 4, 2]));
+            assert_eq!(map.range(9..).len(), 0);
+            assert_eq!(map.range_mut(..3).len(), 3);
+
+            for (_, value) in map.range_mut(7..) {
+                *value = 0;
+            }
+
+            assert!(map.values().copied().eq([0, 1, 2, 4, 5, 6, 7, 0, 0]));
+        }
+
+        #[test]
+        #[should_panic(expected = "range end index 10 out of range for map of length 9")]
+        fn range_out_of_bounds() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+            map.range(5..10);
+        }
+
+        #[test]
+        #[should_panic(expected = "map index starts at 5 but ends at 4")]
+        fn range_start_past_end() {
+            let map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            #[allow(clippy::reversed_empty_ranges)]
+            map.range(5..4);
+        }
+
+        #[test]
+        fn iter_indexed() {
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&3);
+
+            for (index, _, value) in map.iter_indexed_mut() {
+                *value = index as u32;
+            }
+
+            assert_eq!(map.iter_indexed().len(), 9);
+            assert!(map
+                .iter_indexed()
+                .all(|(index, key, _)| map.get_full(key).unwrap().0 == index));
+            assert_eq!(map.iter_indexed().next_back(), Some((8, &9, &8)));
+        }
+
+        #[test]
+        fn const_new() {
+            const EMPTY: Map<String, u32> = Map::new();
+
+            let mut map = EMPTY;
+
+            for x in 0..100u32 {
+                map.insert(x.to_string(), x);
+            }
+
+            check_tree(&map);
+            assert_eq!(map.len(), 100);
+            assert_eq!(map["42"], 42);
+            assert!(EMPTY.is_empty());
+        }
+
+        #[test]
+        fn remove_last() {
+            let mut map: Map<u32, u32> = Map::new();
+
+            for x in 0..200u32 {
+                map.insert(x, x);
+            }
+            map.rebalance();
+
+            for x in (0..200u32).rev() {
+                assert_eq!(map.remove_cost_hint(&x), crate::RemovalKind::Cheap);
+                assert_eq!(map.remove(&x), Some(x));
+
+                check_tree(&map);
+                assert!(map.iter().all(|(key, value)| map.get(key) == Some(value)));
+            }
+
+            assert!(map.is_empty());
+        }
+
+        #[test]
+        fn swap_remove() {
+            for balanced in [false, true] {
+                let mut map: Map<u32, u32> = Map::new();
+
+                for x in 0..200u32 {
+                    map.insert(x, x);
+                }
+                if balanced {
+                    map.rebalance();
+                }
+
+                map.remove_lazy(&199);
+
+                for x in (0..199u32).map(|x| x * 7 % 199) {
+                    assert_eq!(map.swap_remove(&x), Some(x));
+                    assert_eq!(map.swap_remove(&x), None);
+
+                    check_tree(&map);
+                    assert!(map.iter().all(|(key, value)| map.get(key) == Some(value)));
+                }
+
+                assert!(map.is_empty());
+                assert_eq!(map.tombstone_count(), 1);
+            }
+        }
+
+        #[test]
+        fn remove_lazy() {
+            let mut map: Map<u32, u32> = (0..20u32).map(|x| (x, x)).collect();
+
+            for x in (0..20).step_by(2) {
+                assert_eq!(map.remove_lazy(&x), Some(x));
+            }
+
+            check_tree(&map);
+            assert_eq!(map.len(), 10);
+            assert_eq!(map.iter().len(), 10);
+            assert_eq!(map.tombstone_count(), 10);
+            assert!(map.keys().copied().eq((1..20).step_by(2)));
+            assert!(map.clone().into_values().rev().eq((1..20).step_by(2).rev()));
+            assert!(map.values_in_range(2..4).eq(&[5, 7]));
+            assert_eq!(map.entry(5).index(), 2);
+            assert_eq!(map.entry(100).index(), 10);
+
+            map.insert(4, 40);
+            map.remove(&1);
+
+            check_tree(&map);
+            assert_eq!(map.get(&4), Some(&40));
+            assert_eq!(map.keys().last(), Some(&4));
+            assert_eq!(map.clone_compact().tombstone_count(), 0);
+            assert_eq!(map.clone_compact(), map);
+
+            let capacity = map.capacity();
+
+            map.compact();
+
+            check_tree(&map);
+            assert_eq!(map.len(), 10);
+            assert_eq!(map.store.len(), 10);
+            assert_eq!(map.capacity(), capacity);
+            assert_eq!(map.get(&4), Some(&40));
+        }
+
+        #[test]
+        fn rebalance() {
+            let mut map: Map<u64, u64, BuildHasherDefault<IdentityHasher>> = Map::default();
+
+            for x in 0..1000 {
+                map.insert(x, x * 2);
+            }
+
+            map.remove_lazy(&500);
+
+            assert_eq!(map.max_depth(), 1000);
+
+            map.rebalance();
+
+            check_tree(&map);
+            assert!(map.is_balanced(1.0));
+            assert!(map.keys().copied().eq((0..1000).filter(|&x| x != 500)));
+            assert!((0..1000).all(|x| map.contains_key(&x) == (x != 500)));
+
+            map.insert(500, 0);
+            map.insert(1000, 0);
+
+            check_tree(&map);
+            assert!(map.is_balanced(3.0));
+            assert_eq!(map.keys().last(), Some(&1000));
+        }
+
+        #[test]
+        fn from_iter_panic_drops_everything_once() {
+            use std::cell::Cell;
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+            use std::rc::Rc;
+
+            struct Counted(Rc<Cell<usize>>);
+
+            impl Drop for Counted {
+                fn drop(&mut self) {
+                    self.0.set(self.0.get() + 1);
+                }
+            }
+
+            let drops = Rc::new(Cell::new(0));
+            let keys = [1u32, 2, 3, 2, 4];
+
+            let source = keys
+                .iter()
+                .map(|&k| (k, Counted(drops.clone())))
+                .chain(std::iter::once_with(|| panic!("iterator failed")));
+
+            let result = catch_unwind(AssertUnwindSafe(|| source.collect::<Map<u32, Counted>>()));
+
+            assert!(result.is_err());
+            assert_eq!(drops.get(), keys.len());
+        }
+
+        #[test]
+        fn clone_compact_keeps_tree() {
+            let mut map: Map<u32, u32> = Map::with_capacity(1024);
+
+            for x in 0..300 {
+                map.insert(x, x);
+            }
+
+            let replica = map.clone_compact();
+
+            assert_eq!(replica.capacity(), 300);
+            check_tree(&replica);
+
+            for (a, b) in map.store.iter().zip(replica.store.iter()) {
+                assert_eq!(a.left.get(), b.left.get());
+                assert_eq!(a.right.get(), b.right.get());
+            }
+        }
+
+        #[test]
+        fn min_max_value_ties() {
+            let mut map: Map<&str, u32> = Map::new();
+
+            assert_eq!(map.max_value(), None);
+            assert_eq!(map.min_value(), None);
+
+            for &(k, v) in &[("a", 2), ("b", 1), ("c", 5), ("d", 1), ("e", 5)] {
+                map.insert(k, v);
+            }
+
+            assert_eq!(map.max_value(), Some((&"c", &5)));
+            assert_eq!(map.min_value(), Some((&"b", &1)));
+
+            map.insert("c", 4);
+
+            assert_eq!(map.max_value(), Some((&"e", &5)));
+        }
+
+        #[test]
+        fn take_all() {
+            let mut map: Map<u32, u32> = Map::with_capacity(16);
+
+            map.insert(1, 10);
+            map.insert(2, 20);
+
+            let ptr = map.store.as_ptr();
+            let taken = map.take_all();
+
+            assert!(map.is_empty());
+            assert_eq!(map.store.capacity(), 16);
+            assert_eq!(taken.store.as_ptr(), ptr);
+            assert_eq!(taken.get(&2), Some(&20));
+
+            map.insert(3, 30);
+
+            assert_eq!(map.get(&3), Some(&30));
+            assert_eq!(map.get(&1), None);
+        }
+
+        #[test]
+        fn get_or_insert_fill_cannot_reenter() {
+            use std::cell::RefCell;
+
+            let map: RefCell<Map<u32, u32>> = RefCell::new((0..10u32).map(|x| (x, x)).collect());
+
+            let value = *map.borrow_mut().get_or_insert(10, || {
+                assert!(map.try_borrow_mut().is_err());
+                100
+            });
+
+            assert_eq!(value, 100);
+            assert_eq!(map.borrow().get(&10), Some(&100));
+        }
+
+        #[test]
+        fn get_or_insert_fill_panics() {
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+
+            let mut map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                map.get_or_insert(10, || panic!("fill failed"));
+            }));
+
+            assert!(result.is_err());
+            assert_eq!(map.len(), 10);
+            assert_eq!(map.get(&10), None);
+
+            for x in 10..20 {
+                map.insert(x, x);
+            }
+
+            assert!((0..20).all(|x| map.get(&x) == Some(&x)));
+        }
+
+        #[test]
+        fn clear_keeps_capacity() {
+            let mut map: Map<u32, u32> = Map::with_capacity(64);
+
+            for x in 0..64 {
+                map.insert(x, x);
+            }
+
+            let capacity = map.capacity();
+            let ptr = map.store.as_ptr();
+
+            map.clear();
+
+            assert_eq!(map.len(), 0);
+            assert_eq!(map.capacity(), capacity);
+
+            for x in 0..capacity as u32 {
+                map.insert(x + 1000, x);
+            }
+
+            assert_eq!(map.capacity(), capacity);
+            assert_eq!(map.store.as_ptr(), ptr);
+            assert_eq!(map.get(&1000), Some(&0));
+        }
+
+        #[test]
+        fn drain_keeps_capacity() {
+            let mut map: Map<u32, u32> = Map::with_capacity(64);
+
+            for x in 0..64 {
+                map.insert(x, x);
+            }
+
+            let capacity = map.capacity();
+            let ptr = map.store.as_ptr();
+
+            assert!(map.drain().eq((0..64).map(|x| (x, x))));
+            assert_eq!(map.len(), 0);
+            assert_eq!(map.capacity(), capacity);
+
+            for x in 0..capacity as u32 {
+                map.insert(x + 1000, x);
+            }
+
+            assert_eq!(map.capacity(), capacity);
+            assert_eq!(map.store.as_ptr(), ptr);
+            assert_eq!(map.get(&1000), Some(&0));
+        }
+
+        #[test]
+        fn drain_dropped_early() {
+            use std::rc::Rc;
+
+            let value = Rc::new(());
+            let mut map: Map<u32, Rc<()>> = Map::new();
+
+            for x in 0..10 {
+                map.insert(x, value.clone());
+            }
+
+            {
+                let mut drain = map.drain();
+
+                assert_eq!(drain.next().map(|(k, _)| k), Some(0));
+                assert_eq!(drain.next_back().map(|(k, _)| k), Some(9));
+                assert_eq!(drain.len(), 8);
+            }
+
+            assert!(map.is_empty());
+            assert_eq!(Rc::strong_count(&value), 1);
+
+            map.insert(1, value.clone());
+
+            assert_eq!(map.get(&1), Some(&value));
+        }
+
+        #[test]
+        fn values_in_range_clamps() {
+            use core::ops::Bound;
+
+            let map: Map<u32, u32> = (0..10u32).map(|x| (x, x)).collect();
+
+            assert!(map.values_in_range(..).copied().eq(0..10));
+            assert!(map.values_in_range(3..=5).copied().eq(3..6));
+            assert!(map.values_in_range(8..).copied().eq(8..10));
+            assert_eq!(map.values_in_range(20..30).count(), 0);
+            assert_eq!(
+                map.values_in_range((Bound::Included(6), Bound::Excluded(2)))
+                    .count(),
+                0
+            );
+            assert_eq!(map.values_in_range(..=usize::MAX).count(), 10);
+        }
+
+        #[test]
+        fn shrink_to_fit_keeps_links() {
+            let mut map: Map<u32, u32> = Map::with_capacity(4096);
+
+            for x in 0..1000 {
+                map.insert(x, x * 2);
+            }
+
+            let depth = map.max_depth();
+
+            map.shrink_to_fit();
+
+            assert_eq!(map.capacity(), 1000);
+            assert_eq!(map.max_depth(), depth);
+            check_tree(&map);
+
+            assert!((0..1000).all(|x| map.get(&x) == Some(&(x * 2))));
+            assert!(map.keys().copied().eq(0..1000));
+        }
+
+        #[test]
+        fn shrink_to_keeps_links() {
+            let mut map: Map<u32, u32> = Map::with_capacity(4096);
+
+            for x in 0..1000 {
+                map.insert(x, x * 2);
+            }
+
+            map.remove_lazy(&500);
+            map.shrink_to(2000);
+
+            assert_eq!(map.capacity(), 2000);
+
+            map.shrink_to(0);
+
+            // Removed entries still take up space until compacted
+            assert_eq!(map.capacity(), 1000);
+            check_tree(&map);
+
+            map.shrink_to(4096);
+
+            assert_eq!(map.capacity(), 1000);
+            assert!((0..1000).all(|x| x == 500 || map.get(&x) == Some(&(x * 2))));
+        }
+
+        #[test]
+        fn retain() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.retain(|&k, v| {
+                *v *= 2;
+                k % 3 == 0
+            });
+
+            assert_eq!(map.len(), 34);
+
+            for x in 0..100 {
+                if x % 3 == 0 {
+                    assert_eq!(map.get(&x), Some(&(x * 2)));
+                } else {
+                    assert_eq!(map.get(&x), None);
+                }
+            }
+
+            assert!(map.keys().copied().eq((0..100).filter(|x| x % 3 == 0)));
+        }
+
+        #[test]
+        fn retain_keeping_everything_does_not_reallocate() {
+            let mut map: Map<u32, u32> = Map::with_capacity(64);
+
+            for x in 0..40 {
+                map.insert(x, x);
+            }
+
+            let ptr = map.store.as_ptr();
+            let capacity = map.store.capacity();
+
+            let mut calls = 0;
+
+            map.retain(|_, _| {
+                calls += 1;
+                true
+            });
+
+            assert_eq!(calls, 40);
+            assert_eq!(map.store.as_ptr(), ptr);
+            assert_eq!(map.store.capacity(), capacity);
+            assert_eq!(map.len(), 40);
+        }
+
+        #[test]
+        fn try_retain_error_leaves_map_untouched() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+            let ptr = map.store.as_ptr();
+            let mut calls = 0;
+
+            let result = map.try_retain(|&k, _| {
+                calls += 1;
+                if k == 50 {
+                    Err(k)
+                } else {
+                    Ok(k % 2 == 0)
+                }
+            });
+
+            assert_eq!(result, Err(50));
+            assert_eq!(calls, 51);
+            assert_eq!(map.store.as_ptr(), ptr);
+            assert!(map.keys().copied().eq(0..100));
+
+            assert_eq!(map.try_retain(|&k, _| Ok::<_, ()>(k % 2 == 0)), Ok(()));
+            assert!(map.keys().copied().eq((0..100).step_by(2)));
+            assert!((0..100).all(|x| map.contains_key(&x) == (x % 2 == 0)));
+        }
+
+        #[test]
+        fn retain_keys_mut() {
+            let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+
+            map.remove(&7);
+            map.retain_keys_mut(|k, _| {
+                *k /= 10;
+                *k != 5
+            });
+
+            // Keys collapse onto their first occurrence with the last value
+            assert_eq!(map.len(), 9);
+            assert!(map.keys().copied().eq((0..10).filter(|&x| x != 5)));
+
+            for x in 0..10 {
+                if x == 5 {
+                    assert_eq!(map.get(&x), None);
+                } else {
+                    assert_eq!(map.get(&x), Some(&(x * 10 + 9)));
+                }
+            }
+        }
+    }
+
+    mod int_map {
+        use super::check_tree;
+        use crate::IntMap;
+
+        #[test]
+        fn dense_keys_stay_balanced() {
+            let mut map = IntMap::default();
+
+            for x in 0..10_000u32 {
+                map.insert(x, x);
+            }
+
+            check_tree(&map);
+            assert!(map.is_balanced(3.0));
+            assert!((0..10_000u32).all(|x| map[&x] == x));
+        }
+    }
+
+    mod frozen {
+        use super::IdentityHasher;
+        use crate::Map;
+        use core::hash::BuildHasherDefault;
+
+        #[test]
+        fn balanced() {
+            let mut map: Map<u64, u64, BuildHasherDefault<IdentityHasher>> = Map::default();
+
+            for x in 0..1000 {
+                map.insert(x, x * 2);
+            }
+
+            map.remove(&500);
+
+            assert_eq!(map.max_depth(), 999);
+            assert!(!map.is_reasonably_balanced());
+
+            let frozen = map.freeze();
+
+            assert_eq!(frozen.len(), 999);
+            assert_eq!(frozen.map.store.capacity(), 999);
+            assert!(frozen.map.is_balanced(1.0));
+
+            for x in 0..1000 {
+                if x == 500 {
+                    assert_eq!(frozen.get(&x), None);
+                } else {
+                    assert_eq!(frozen.get(&x), Some(&(x * 2)));
+                }
+            }
+
+            assert!(frozen
+                .iter()
+                .map(|(&k, _)| k)
+                .eq((0..1000).filter(|&x| x != 500)));
+        }
+
+        #[test]
+        fn balanced_with_root_in_the_middle() {
+            let mut map: Map<u64, u64, BuildHasherDefault<IdentityHasher>> = Map::default();
+
+            map.insert(500, 0);
+
+            for x in (0..1000).rev() {
+                map.insert(x, x);
+            }
+
+            let frozen = map.freeze();
+
+            assert!(frozen.map.is_balanced(1.0));
+            assert!((0..1000).all(|x| frozen.contains_key(&x)));
+        }
+    }
+
+    mod compact {
+        use crate::compact::Vec;
+        use std::rc::Rc;
+
+        #[test]
+        fn const_new() {
+            const EMPTY: Vec<String> = Vec::new();
+            const UNITS: Vec<()> = Vec::new();
+
+            let mut strings = EMPTY;
+            let mut units = UNITS;
+
+            assert_eq!(
+                strings.capacity(),
+                std::vec::Vec::<String>::new().capacity()
+            );
+            assert_eq!(units.capacity(), u32::MAX as usize);
+
+            for x in 0..100 {
+                strings.push(x.to_string());
+                units.push(());
+            }
+
+            assert_eq!(strings.len(), 100);
+            assert_eq!(units.len(), 100);
+            assert_eq!(strings[99], "99");
+        }
+
+        #[test]
+        fn truncate_and_slices() {
+            let rc = Rc::new(());
+            let mut vec: Vec<Rc<()>> = Vec::with_capacity(10);
+
+            for _ in 0..10 {
+                vec.push(rc.clone());
+            }
+
+            vec.truncate(20);
+            assert_eq!(vec.len(), 10);
+
+            vec.truncate(4);
+            assert_eq!(vec.len(), 4);
+            assert_eq!(vec.capacity(), 10);
+            assert_eq!(Rc::strong_count(&rc), 5);
+
+            vec.as_mut_slice()[0] = Rc::new(());
+            assert_eq!(Rc::strong_count(&rc), 4);
+            assert_eq!(vec.as_slice().len(), 4);
+
+            vec.shrink_to_fit();
+            assert_eq!(vec.capacity(), 4);
+
+            vec.reserve_exact(3);
+            assert_eq!(vec.capacity(), 7);
+        }
+
+        #[test]
+        fn try_with_capacity() {
+            use crate::TryReserveError;
+
+            let vec: Vec<u64> = Vec::try_with_capacity(100).unwrap();
+
+            assert_eq!(vec.capacity(), 100);
+            assert_eq!(
+                Vec::<u64>::try_with_capacity(u32::MAX as usize + 1).unwrap_err(),
+                TryReserveError::CapacityOverflow
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "capacity out of bounds")]
+        fn with_capacity_overflow() {
+            let _: Vec<()> = Vec::with_capacity(u32::MAX as usize + 1);
+        }
+
+        #[test]
+        fn drain_full_range() {
+            let mut vec: Vec<String> = (0..10).map(|x| x.to_string()).collect();
+            let capacity = vec.capacity();
+
+            let drained: std::vec::Vec<_> = vec.drain(..).collect();
+
+            assert_eq!(drained.len(), 10);
+            assert_eq!(drained[9], "9");
+            assert!(vec.is_empty());
+            assert_eq!(vec.capacity(), capacity);
+        }
+
+        #[test]
+        fn drain_partial_range() {
+            let mut vec: Vec<u32> = (0..10).collect();
+
+            assert!(vec.drain(2..5).eq(2..5));
+            assert_eq!(&vec[..], &[0, 1, 5, 6, 7, 8, 9]);
+
+            assert!(vec.drain(4..=5).rev().eq(vec![8, 7]));
+            assert_eq!(&vec[..], &[0, 1, 5, 6, 9]);
+
+            assert_eq!(vec.drain(5..).count(), 0);
+            assert_eq!(vec.len(), 5);
+        }
+
+        #[test]
+        fn drain_dropped_early() {
+            let rc = Rc::new(());
+            let mut vec: Vec<Rc<()>> = (0..10).map(|_| rc.clone()).collect();
+
+            let mut drain = vec.drain(1..8);
+
+            drain.next();
+            drain.next_back();
+            drop(drain);
+
+            assert_eq!(vec.len(), 3);
+            assert_eq!(Rc::strong_count(&rc), 4);
+
+            vec.push(rc.clone());
+            core::mem::forget(vec.drain(1..));
+
+            // Leaked elements are never dropped, but the vector is left intact
+            assert_eq!(vec.len(), 1);
+            assert_eq!(Rc::strong_count(&rc), 5);
+        }
+
+        #[test]
+        fn retain() {
+            let mut vec: Vec<String> = (0..10).map(|x| x.to_string()).collect();
+
+            vec.retain(|x| x != "3" && x != "4");
+            vec.retain_mut(|x| {
+                x.push('!');
+                x != "8!"
+            });
+
+            assert_eq!(&vec[..], &["0!", "1!", "2!", "5!", "6!", "7!", "9!"]);
+        }
+
+        #[test]
+        fn retain_panicking() {
+            let rc = Rc::new(());
+            let mut vec: Vec<(u32, Rc<()>)> = (0..10).map(|x| (x, rc.clone())).collect();
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                vec.retain(|&(x, _)| {
+                    assert!(x < 6);
+                    x % 2 == 0
+                })
+            }));
+
+            assert!(result.is_err());
+            assert!(vec.iter().map(|&(x, _)| x).eq(vec![0, 2, 4, 6, 7, 8, 9]));
+            assert_eq!(Rc::strong_count(&rc), 8);
+        }
+
+        #[test]
+        fn clone_from_panicking_drop() {
+            #[derive(Clone)]
+            struct Bomb(bool);
+
+            impl Drop for Bomb {
+                fn drop(&mut self) {
+                    if self.0 {
+                        self.0 = false;
+                        panic!("boom");
+                    }
                 }
-            } else {
-                return None;
             }
+
+            let source: Vec<Bomb> = (0..2).map(|_| Bomb(false)).collect();
+            let mut vec: Vec<Bomb> = (0..4).map(|x| Bomb(x == 3)).collect();
+
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| vec.clone_from(&source)));
+
+            // The buffer survives the panic and is only freed once, below
+            assert!(result.is_err());
+            assert_eq!(vec.len(), 2);
+        }
+
+        #[test]
+        fn raw_parts_round_trip() {
+            let mut vec: Vec<String> = Vec::with_capacity(16);
+
+            vec.push("a".into());
+            vec.push("b".into());
+
+            let (ptr, len, cap) = vec.into_raw_parts();
+
+            assert_eq!((len, cap), (2, 16));
+
+            let vec = unsafe { Vec::from_raw_parts(ptr, len, cap) };
+
+            assert_eq!(&vec[..], &["a", "b"]);
+            assert_eq!(vec.capacity(), 16);
+        }
+
+        #[test]
+        #[should_panic(expected = "drain range end out of bounds")]
+        fn drain_out_of_bounds() {
+            let mut vec: Vec<u32> = (0..10).collect();
+
+            vec.drain(5..11);
+        }
+
+        #[test]
+        #[should_panic(expected = "split_off index out of bounds")]
+        fn split_off_out_of_bounds() {
+            let mut vec: Vec<String> = Vec::new();
+
+            vec.push("a".into());
+            vec.push("b".into());
+            vec.split_off(5);
         }
     }
-    #[inline]
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
-    }
-}
 
-impl<K, Q: ?Sized, V> Index<&Q> for Map<K, V>
-where
-    K: Eq + Hash + Borrow<Q>,
-    Q: Eq + Hash,
-{
-    type Output = V;
+    /// Exercises every path doing unchecked indexing into the store, meant
+    /// to be run under Miri with `cargo +nightly miri test unchecked`.
+    mod unchecked {
+        use crate::{Entry, Map, RawEntryMut};
 
-    /// Returns a reference to the value corresponding to the supplied key.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the key is not present in the HashMap.
-    fn index(&self, key: &Q) -> &V {
-        self.get(key).expect("Key not found in Map")
-    }
-}
+        #[test]
+        fn lookups_and_mutations() {
+            let mut map: Map<String, usize> = Map::new();
 
-impl<'json, IK, IV, K, V> FromIterator<(IK, IV)> for Map<K, V>
-where
-    IK: Into<K>,
-    IV: Into<V>,
-    K: Hash + Eq,
-{
-    fn from_iter<I>(iter: I) -> Self
-    where
-        I: IntoIterator<Item = (IK, IV)>,
-    {
-        let iter = iter.into_iter();
-        let mut map = Map::with_capacity(iter.size_hint().0);
+            for x in 0..40 {
+                map.insert(x.to_string(), x);
+            }
+            for x in (0..40).step_by(2) {
+                *map.get_mut(&x.to_string()).unwrap() += 100;
+            }
+            for x in (0..40).step_by(3) {
+                assert!(map.remove(&x.to_string()).is_some());
+            }
 
-        for (key, value) in iter {
-            map.insert(key.into(), value.into());
+            *map.get_or_insert("fresh".into(), || 1) += 1;
+
+            match map.entry("7".into()) {
+                Entry::Occupied(mut o) => *o.get_mut() += 1,
+                Entry::Vacant(_) => unreachable!(),
+            }
+            match map.raw_entry_mut().from_key("8") {
+                RawEntryMut::Occupied(o) => assert_eq!(o.remove(), 108),
+                RawEntryMut::Vacant(_) => unreachable!(),
+            }
+
+            for x in 0..40 {
+                let expected = match x {
+                    8 => None,
+                    x if x % 3 == 0 => None,
+                    7 => Some(8),
+                    x if x % 2 == 0 => Some(x + 100),
+                    x => Some(x),
+                };
+
+                assert_eq!(map.get(&x.to_string()).copied(), expected);
+                assert_eq!(map.contains_key(&x.to_string()), expected.is_some());
+            }
+
+            assert_eq!(map.get("fresh"), Some(&2));
+            assert_eq!(map.iter().count(), map.len());
+            assert_eq!(map.iter_mut().rev().count(), map.len());
+            assert_eq!(map.clone().into_iter().count(), map.len());
+
+            map.retain(|k, _| k.len() == 1);
+            map.retain_keys_mut(|k, _| {
+                k.push('!');
+                true
+            });
+
+            assert!(map.keys().all(|k| k.ends_with('!')));
+            assert_eq!(map.get("1!"), Some(&1));
         }
 
-        map
-    }
-}
+        #[test]
+        #[cfg(all(debug_assertions, not(feature = "alloc-vec")))]
+        #[should_panic(expected = "compact Vec index out of bounds")]
+        fn corrupt_link_panics_in_debug() {
+            use core::num::NonZeroU32;
 
-// Because keys can inserted in different order, the safe way to
-// compare `Map`s is to iterate over one and check if the other
-// has all the same keys.
-impl<K, V> PartialEq for Map<K, V>
-where
-    K: PartialEq,
-    V: PartialEq,
-{
-    fn eq(&self, other: &Self) -> bool {
-        if self.len() != other.len() {
-            return false;
+            let mut map: Map<u32, u32> = Map::new();
+
+            map.insert(1, 1);
+            map.store[0].left.set(NonZeroU32::new(1000));
+            map.store[0].right.set(NonZeroU32::new(1000));
+
+            map.get(&2);
         }
+    }
+
+    /// Randomized test applying sequences of mutations to a `Map` and
+    /// comparing it against a plain `Vec<(K, V)>` model after every step.
+    mod model {
+        use super::check_tree;
+        use crate::{Map, MergePolicy};
+
+        const KEYS: u16 = 48;
+        const SEEDS: u64 = if cfg!(miri) { 2 } else { 64 };
+        const STEPS: usize = if cfg!(miri) { 64 } else { 256 };
+
+        /// xorshift64*, good enough to shuffle operations around.
+        struct Rng(u64);
+
+        impl Rng {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 >> 12;
+                self.0 ^= self.0 << 25;
+                self.0 ^= self.0 >> 27;
+                self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+            }
+
+            fn below(&mut self, n: u64) -> u64 {
+                self.next() % n
+            }
 
-        for (a, b) in self.iter().zip(other.iter()) {
-            if a != b {
-                return false;
+            fn key(&mut self) -> u16 {
+                self.below(KEYS as u64) as u16
             }
         }
 
-        return true;
-    }
-}
+        type Model = Vec<(u16, u32)>;
 
-impl<K, V> Eq for Map<K, V>
-where
-    K: Eq,
-    V: Eq,
-{
-}
+        fn position(model: &Model, key: u16) -> Option<usize> {
+            model.iter().position(|&(k, _)| k == key)
+        }
 
-// Because keys can inserted in different order, the safe way to
-// compare `Map`s is to iterate over one and check if the other
-// has all the same keys.
-impl<K, V> PartialOrd for Map<K, V>
-where
-    K: PartialOrd,
-    V: PartialOrd,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        for (a, b) in self.iter().zip(other.iter()) {
-            match a.partial_cmp(&b) {
-                Some(Ordering::Equal) => {}
-                other => return other,
+        fn model_insert(model: &mut Model, key: u16, value: u32) -> Option<u32> {
+            match position(model, key) {
+                Some(idx) => Some(std::mem::replace(&mut model[idx].1, value)),
+                None => {
+                    model.push((key, value));
+                    None
+                }
             }
         }
 
-        return self.len().partial_cmp(&other.len());
-    }
-}
+        fn check(map: &Map<u16, u32>, model: &Model) {
+            check_tree(map);
 
-impl<K, V> Ord for Map<K, V>
-where
-    K: Ord,
-    V: Ord,
-{
-    fn cmp(&self, other: &Self) -> Ordering {
-        for (a, b) in self.iter().zip(other.iter()) {
-            match a.cmp(&b) {
-                Ordering::Equal => {}
-                other => return other,
+            assert_eq!(map.len(), model.len());
+            assert_eq!(map.iter().len(), model.len());
+            assert!(map.iter().map(|(&k, &v)| (k, v)).eq(model.iter().copied()));
+
+            for key in 0..KEYS {
+                let expected = position(model, key).map(|idx| &model[idx].1);
+
+                assert_eq!(map.get(&key), expected);
+                assert_eq!(map.contains_key(&key), expected.is_some());
             }
         }
 
-        return self.len().cmp(&other.len());
-    }
-}
+        fn step(rng: &mut Rng, map: &mut Map<u16, u32>, model: &mut Model) {
+            match rng.below(10) {
+                0 | 1 => {
+                    let (key, value) = (rng.key(), rng.next() as u32);
 
-/// An iterator over the entries of a `Map`.
-///
-/// This struct is created by the [`iter`](./struct.Map.html#method.iter)
-/// method on [`Map`](./struct.Map.html). See its documentation for more.
-pub struct Iter<'a, K, V> {
-    inner: slice::Iter<'a, Node<K, V>>,
-}
+                    assert_eq!(map.insert(key, value), model_insert(model, key, value));
+                }
+                2 | 3 => {
+                    let key = rng.key();
+                    let expected = position(model, key).map(|idx| model.remove(idx).1);
 
-/// A mutable iterator over the entries of a `Map`.
-///
-/// This struct is created by the [`iter_mut`](./struct.Map.html#method.iter_mut)
-/// method on [`Map`](./struct.Map.html). See its documentation for more.
-pub struct IterMut<'a, K, V> {
-    inner: slice::IterMut<'a, Node<K, V>>,
-}
+                    assert_eq!(map.remove(&key), expected);
+                }
+                4 => {
+                    let (key, value) = (rng.key(), rng.next() as u32);
 
-impl<K, V> Iter<'_, K, V> {
-    /// Create an empty iterator that always returns `None`
-    pub fn empty() -> Self {
-        Iter { inner: [].iter() }
-    }
-}
+                    if position(model, key).is_none() {
+                        model.push((key, value));
+                    }
 
-impl<'i, K, V> Iterator for Iter<'i, K, V> {
-    type Item = (&'i K, &'i V);
+                    let idx = position(model, key).unwrap();
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Some(node) = self.inner.next() {
-            let value = match node.value {
-                Some(ref value) => value,
-                None => continue,
-            };
+                    assert_eq!(*map.get_or_insert(key, || value), model[idx].1);
+                }
+                5 => {
+                    let modulo = rng.below(4) as u16 + 2;
 
-            return Some((&node.key, value));
-        }
+                    map.retain(|&k, v| {
+                        *v = v.wrapping_add(1);
+                        k % modulo != 0
+                    });
 
-        None
-    }
-}
+                    model.retain(|&(k, _)| k % modulo != 0);
 
-impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
-    #[inline]
-    fn next_back(&mut self) -> Option<Self::Item> {
-        while let Some(node) = self.inner.next_back() {
-            let value = match node.value {
-                Some(ref value) => value,
-                None => continue,
-            };
+                    for (_, v) in model.iter_mut() {
+                        *v = v.wrapping_add(1);
+                    }
+                }
+                6 => {
+                    let shift = rng.key();
 
-            return Some((&node.key, value));
-        }
+                    map.retain_keys_mut(|k, _| {
+                        *k = (*k + shift) % KEYS / 2;
+                        *k != 0
+                    });
 
-        None
-    }
-}
+                    let old = std::mem::take(model);
 
-impl<K, V> ExactSizeIterator for Iter<'_, K, V> {
-    fn len(&self) -> usize {
-        self.inner.len()
-    }
-}
+                    for (k, v) in old {
+                        let k = (k + shift) % KEYS / 2;
 
-impl<K, V> IterMut<'_, K, V> {
-    /// Create an empty iterator that always returns `None`
-    pub fn empty() -> Self {
-        IterMut {
-            inner: [].iter_mut(),
+                        if k != 0 {
+                            model_insert(model, k, v);
+                        }
+                    }
+                }
+                7 | 8 => {
+                    let key = rng.key();
+                    let expected = position(model, key).map(|idx| model.remove(idx).1);
+
+                    assert_eq!(map.remove_lazy(&key), expected);
+                }
+                9 => {
+                    map.compact();
+
+                    assert_eq!(map.tombstone_count(), 0);
+                }
+                _ => {
+                    let incoming: Model = (0..rng.below(8))
+                        .map(|_| (rng.key(), rng.next() as u32))
+                        .fold(Vec::new(), |mut incoming, (k, v)| {
+                            model_insert(&mut incoming, k, v);
+                            incoming
+                        });
+                    let other: Map<u16, u32> = incoming.iter().copied().collect();
+
+                    let policy = match rng.below(3) {
+                        0 => MergePolicy::KeepExisting,
+                        1 => MergePolicy::TakeIncoming,
+                        _ => MergePolicy::Error,
+                    };
+
+                    let collision = incoming
+                        .iter()
+                        .find(|&&(k, _)| position(model, k).is_some());
+
+                    match policy {
+                        MergePolicy::Error => {
+                            let result = map.merge(other, policy).map_err(|err| err.key);
+
+                            match collision {
+                                Some(&(k, _)) => assert_eq!(result, Err(k)),
+                                None => {
+                                    assert_eq!(result, Ok(()));
+                                    model.extend(incoming);
+                                }
+                            }
+                        }
+                        MergePolicy::KeepExisting => {
+                            assert_eq!(map.merge(other, policy), Ok(()));
+
+                            for (k, v) in incoming {
+                                if position(model, k).is_none() {
+                                    model.push((k, v));
+                                }
+                            }
+                        }
+                        MergePolicy::TakeIncoming => {
+                            assert_eq!(map.merge(other, policy), Ok(()));
+
+                            for (k, v) in incoming {
+                                model_insert(model, k, v);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn random_mutations() {
+            for seed in 1..=SEEDS {
+                let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                let mut map = Map::new();
+                let mut model = Model::new();
+
+                for _ in 0..STEPS {
+                    step(&mut rng, &mut map, &mut model);
+                    check(&map, &model);
+                }
+            }
         }
     }
-}
 
-impl<'a, K, V> Iterator for IterMut<'a, K, V> {
-    type Item = (&'a K, &'a mut V);
+    #[cfg(feature = "serde")]
+    mod serde {
+        use super::check_tree;
+        use crate::Map;
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Some(node) = self.inner.next() {
-            let value = match node.value {
-                Some(ref mut value) => value,
-                None => continue,
-            };
+        #[test]
+        fn round_trip_keeps_order() {
+            let json = r#"{"z":1,"a":2,"m":3}"#;
 
-            return Some((&node.key, value));
-        }
+            let map: Map<String, u32> = serde_json::from_str(json).unwrap();
 
-        None
-    }
-}
+            check_tree(&map);
+            assert!(map.keys().eq(&["z", "a", "m"]));
+            assert_eq!(serde_json::to_string(&map).unwrap(), json);
+        }
 
-impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
-    #[inline]
-    fn next_back(&mut self) -> Option<Self::Item> {
-        while let Some(node) = self.inner.next_back() {
-            let value = match node.value {
-                Some(ref mut value) => value,
-                None => continue,
-            };
+        #[test]
+        fn duplicate_keys_overwrite() {
+            let map: Map<String, u32> = serde_json::from_str(r#"{"a":1,"b":2,"a":3}"#).unwrap();
 
-            return Some((&node.key, value));
+            assert_eq!(serde_json::to_string(&map).unwrap(), r#"{"a":3,"b":2}"#);
         }
 
-        None
-    }
-}
+        #[test]
+        fn serialize_sorted() {
+            let mut map: Map<u32, u32> = (0..10u32).rev().map(|x| (x, x)).collect();
 
-impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {
-    fn len(&self) -> usize {
-        self.inner.len()
+            map.remove_lazy(&5);
+
+            let sorted = serde_json::to_string(&map.serialize_sorted()).unwrap();
+            let expected: Map<u32, u32> = (0..10u32).filter(|&x| x != 5).map(|x| (x, x)).collect();
+
+            assert_eq!(sorted, serde_json::to_string(&expected).unwrap());
+            assert!(map.keys().copied().eq((0..10u32).rev().filter(|&x| x != 5)));
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    mod map {
+    #[cfg(feature = "std")]
+    mod std_collections {
+        use super::check_tree;
         use crate::Map;
+        use std::collections::HashMap;
 
         #[test]
-        fn empty() {
-            let map: Map<&str, u64> = Map::new();
+        fn into_hash_map() {
+            let map: Map<u32, u32> = (0..100u32).map(|x| (x, x * 2)).collect();
+            let hash_map: HashMap<u32, u32> = map.clone().into();
 
-            assert_eq!(map.get("foo"), None);
-            assert_eq!(map.len(), 0);
-            assert_eq!(map.is_empty(), true);
+            assert_eq!(hash_map.len(), 100);
+            assert!(map.iter().all(|(key, value)| hash_map[key] == *value));
         }
 
         #[test]
-        fn simple() {
-            let mut map: Map<&str, u64> = Map::new();
+        fn from_hash_map() {
+            let hash_map: HashMap<u32, u32> = (0..100u32).map(|x| (x, x * 2)).collect();
+            let map: Map<u32, u32> = hash_map.clone().into();
 
-            map.insert("foo", 42);
+            check_tree(&map);
+            assert!(map.keys().eq(hash_map.keys()));
+            assert!(hash_map.iter().all(|(key, value)| map[key] == *value));
+        }
+    }
 
-            assert_eq!(map.get("foo"), Some(&42));
-            assert_eq!(map.len(), 1);
-            assert_eq!(map.is_empty(), false);
+    #[cfg(feature = "std")]
+    mod persist {
+        use super::check_tree;
+        use crate::Map;
+        use std::io::ErrorKind;
+
+        #[test]
+        fn round_trip() {
+            let mut map: Map<String, Vec<u8>> = Map::new();
+
+            for x in 0..100u8 {
+                map.insert(format!("key {}", x), vec![x; x as usize]);
+            }
+            map.remove_lazy("key 50");
+
+            let mut buf = Vec::new();
+            map.write_to(&mut buf).unwrap();
+
+            let loaded: Map<String, Vec<u8>> = Map::read_from(&mut buf.as_slice()).unwrap();
+
+            check_tree(&loaded);
+            assert_eq!(loaded.len(), 99);
+            assert!(loaded.eq_ordered(&map));
         }
 
         #[test]
-        fn iter() {
-            let mut map: Map<&str, u64> = Map::new();
+        fn primitives() {
+            let mut map: Map<i64, f64> = Map::new();
+            map.insert(-1, 0.5);
+            map.insert(i64::MAX, f64::INFINITY);
 
-            let elements = [("foo", 0123), ("bar", 1234), ("baz", 2345), ("qux", 3456)];
+            let mut buf = Vec::new();
+            map.write_to(&mut buf).unwrap();
 
-            for &(k, v) in &elements {
-                assert!(map.insert(k, v).is_none());
-            }
+            assert_eq!(buf.len(), 8 + 2 * 16);
 
-            assert_eq!(map.iter().len(), elements.len());
-            assert_eq!(map.clone().into_iter().len(), elements.len());
+            let loaded: Map<i64, f64> = Map::read_from(&mut buf.as_slice()).unwrap();
 
-            assert_eq!(
-                &map.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
-                &elements
-            );
-            assert_eq!(&map.clone().into_iter().collect::<Vec<_>>(), &elements);
+            assert!(loaded.eq_ordered(&map));
 
-            let reversed_elements = elements.iter().cloned().rev().collect::<Vec<_>>();
+            let map: Map<char, bool> = "ordnung".chars().map(|c| (c, c > 'n')).collect();
 
-            assert_eq!(
-                &map.iter().rev().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
-                &reversed_elements
-            );
-            assert_eq!(
-                &map.clone().into_iter().rev().collect::<Vec<_>>(),
-                &reversed_elements
-            );
+            buf.clear();
+            map.write_to(&mut buf).unwrap();
+
+            let loaded: Map<char, bool> = Map::read_from(&mut buf.as_slice()).unwrap();
+
+            assert!(loaded.eq_ordered(&map));
+        }
+
+        #[test]
+        fn invalid_input() {
+            let mut map: Map<&str, bool> = Map::new();
+            map.insert("a", true);
+
+            let mut buf = Vec::new();
+            map.write_to(&mut buf).unwrap();
+
+            let truncated = Map::<String, bool>::read_from(&mut &buf[..buf.len() - 1]);
+            assert_eq!(truncated.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+            let mut bad_bool = buf.clone();
+            *bad_bool.last_mut().unwrap() = 2;
+            let bad_bool = Map::<String, bool>::read_from(&mut bad_bool.as_slice());
+            assert_eq!(bad_bool.unwrap_err().kind(), ErrorKind::InvalidData);
+
+            let mut bad_utf8 = buf.clone();
+            bad_utf8[16] = 0xff;
+            let bad_utf8 = Map::<String, bool>::read_from(&mut bad_utf8.as_slice());
+            assert_eq!(bad_utf8.unwrap_err().kind(), ErrorKind::InvalidData);
+
+            let mut huge_len = buf;
+            huge_len[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+            let huge_len = Map::<String, bool>::read_from(&mut huge_len.as_slice());
+            assert_eq!(huge_len.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    mod rayon {
+        use crate::Map;
+        use rayon::prelude::*;
+
+        #[test]
+        fn skips_removed_entries() {
+            let mut map: Map<u32, u32> = (0..1000u32).map(|x| (x, x)).collect();
+
+            map.remove_lazy(&10);
+            map.par_values_mut().for_each(|value| *value += 1);
+
+            assert_eq!(map.par_iter().count(), 999);
+            assert!(map.par_iter().all(|(key, value)| *value == key + 1));
         }
     }
 
@@ -1188,7 +8832,7 @@ mod tests {
 
             assert_eq!(map.get("foo"), None);
             assert_eq!(map.len(), 0);
-            assert_eq!(map.is_empty(), true);
+            assert!(map.is_empty());
         }
 
         #[test]
@@ -1199,7 +8843,7 @@ mod tests {
 
             assert_eq!(map.get("foo"), Some(&"foo"));
             assert_eq!(map.len(), 1);
-            assert_eq!(map.is_empty(), false);
+            assert!(!map.is_empty());
         }
 
         #[test]
@@ -0,0 +1,39 @@
+/// Creates a [`Map`](./struct.Map.html) containing the given entries, in
+/// the order they are listed.
+///
+/// Every key and value is converted with `Into`, so string literals can be
+/// used for `String` keys. Since the conversion needs to know the target
+/// types, the type of the map usually has to be spelled out, and integer
+/// literals need a suffix unless the target type is `i32`.
+///
+/// # Examples
+///
+/// ```rust
+/// use ordnung::{map, Map};
+///
+/// let map: Map<String, u64> = map! {
+///     "a" => 1u8,
+///     "b" => 2u32,
+/// };
+///
+/// assert_eq!(map["a"], 1);
+/// assert!(map.keys().eq(&["a", "b"]));
+///
+/// let empty: Map<String, u64> = map! {};
+///
+/// assert!(empty.is_empty());
+/// ```
+#[macro_export]
+macro_rules! map {
+    (@unit $($x:tt)*) => (());
+    () => ($crate::Map::new());
+    ($($key:expr => $value:expr),+ $(,)?) => {{
+        let mut map = $crate::Map::with_capacity(<[()]>::len(&[$($crate::map!(@unit $key)),+]));
+
+        $(
+            map.insert($key.into(), $value.into());
+        )+
+
+        map
+    }};
+}
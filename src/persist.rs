@@ -0,0 +1,237 @@
+//! A simple binary format for saving a `Map` and loading it back, in
+//! insertion order, available with the `std` feature. Unlike the optional
+//! `serde` support, it doesn't need any dependencies.
+//!
+//! A map is written as the number of entries, followed by every key and
+//! value in insertion order. Integers, floats, and `char`s are written
+//! little-endian at their full width, with `usize` and `isize` widened to
+//! 64 bits, and `bool`s as a single byte. Strings and byte vectors are
+//! written as their length in bytes, followed by the bytes.
+//!
+//! ```rust
+//! use ordnung::Map;
+//!
+//! let mut map: Map<String, u32> = Map::new();
+//! map.insert("b".into(), 2);
+//! map.insert("a".into(), 1);
+//!
+//! let mut buf = Vec::new();
+//! map.write_to(&mut buf).unwrap();
+//!
+//! let loaded: Map<String, u32> = Map::read_from(&mut buf.as_slice()).unwrap();
+//!
+//! assert!(loaded.eq_ordered(&map));
+//! ```
+
+use super::*;
+use core::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+/// Types that can be written as keys or values of a map with
+/// [`write_to`](../struct.Map.html#method.write_to).
+pub trait Encode {
+    /// Writes `self` to `w`.
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()>;
+}
+
+/// Types that can be read as keys or values of a map with
+/// [`read_from`](../struct.Map.html#method.read_from).
+pub trait Decode: Sized {
+    /// Reads a value from `r`, in the format it was written in by
+    /// [`Encode`](trait.Encode.html).
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                #[inline]
+                fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+                    w.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $t {
+                #[inline]
+                fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+                    let mut bytes = [0; core::mem::size_of::<$t>()];
+
+                    r.read_exact(&mut bytes)?;
+
+                    Ok(<$t>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Encode for usize {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u64).encode(w)
+    }
+}
+
+impl Decode for usize {
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        usize::try_from(u64::decode(r)?).map_err(|_| invalid_data("usize out of range"))
+    }
+}
+
+impl Encode for isize {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        (*self as i64).encode(w)
+    }
+}
+
+impl Decode for isize {
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        isize::try_from(i64::decode(r)?).map_err(|_| invalid_data("isize out of range"))
+    }
+}
+
+impl Encode for bool {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u8).encode(w)
+    }
+}
+
+impl Decode for bool {
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        match u8::decode(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_data("invalid bool")),
+        }
+    }
+}
+
+impl Encode for char {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u32).encode(w)
+    }
+}
+
+impl Decode for char {
+    #[inline]
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        char::from_u32(u32::decode(r)?).ok_or_else(|| invalid_data("invalid char"))
+    }
+}
+
+impl Encode for [u8] {
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        w.write_all(self)
+    }
+}
+
+impl Encode for alloc::vec::Vec<u8> {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.as_slice().encode(w)
+    }
+}
+
+impl Decode for alloc::vec::Vec<u8> {
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        let len = u64::decode(r)?;
+        let mut bytes = alloc::vec::Vec::new();
+
+        // Read through `take` rather than allocating `len` up front, a
+        // corrupted length shouldn't be able to exhaust memory.
+        r.take(len).read_to_end(&mut bytes)?;
+
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl Encode for str {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.as_bytes().encode(w)
+    }
+}
+
+impl Encode for String {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.as_str().encode(w)
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        String::from_utf8(Decode::decode(r)?).map_err(|_| invalid_data("invalid UTF-8"))
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    #[inline]
+    fn encode<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        (**self).encode(w)
+    }
+}
+
+impl<K, V, S> Map<K, V, S>
+where
+    K: Encode,
+    V: Encode,
+{
+    /// Writes all entries of the map to `w` in insertion order, in the
+    /// format described in the [`persist`](persist/index.html) module.
+    /// Requires the `std` feature.
+    ///
+    /// Nothing is buffered, so wrapping files in a `BufWriter` is a good
+    /// idea.
+    pub fn write_to<W: Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+
+        for (key, value) in self.iter() {
+            key.encode(w)?;
+            value.encode(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<K, V, S> Map<K, V, S>
+where
+    K: Decode + Hash + Eq,
+    V: Decode,
+    S: BuildHasher + Default,
+{
+    /// Reads a map written with [`write_to`](#method.write_to) from `r`,
+    /// inserting the entries in the order they were written. Requires the
+    /// `std` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from reading `r`, or an error of kind
+    /// `InvalidData` if an entry can't be decoded.
+    pub fn read_from<R: Read + ?Sized>(r: &mut R) -> io::Result<Self> {
+        let len = u64::decode(r)?;
+
+        (0..len)
+            .map(|_| Ok((K::decode(r)?, V::decode(r)?)))
+            .collect()
+    }
+}
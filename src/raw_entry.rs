@@ -8,8 +8,8 @@ use core::mem;
 /// See the [`VecMap::raw_entry_mut`] docs for usage examples.
 ///
 /// [`VecMap::raw_entry_mut`]: struct.VecMap.html#method.raw_entry_mut
-pub struct RawEntryBuilderMut<'a, K, V, H> {
-    pub(crate) map: &'a mut Map<K, V, H>,
+pub struct RawEntryBuilderMut<'a, K, V, S> {
+    pub(crate) map: &'a mut Map<K, V, S>,
 }
 
 /// A view into a single entry in a map, which may either be vacant or occupied.
@@ -23,41 +23,28 @@ pub struct RawEntryBuilderMut<'a, K, V, H> {
 /// [`Entry`]: enum.Entry.html
 /// [`raw_entry_mut`]: struct.VecMap.html#method.raw_entry_mut
 /// [`RawEntryBuilderMut`]: struct.RawEntryBuilderMut.html
-pub enum RawEntryMut<'a, K, V, H> {
+pub enum RawEntryMut<'a, K, V, S> {
     /// An occupied entry.
-    Occupied(RawOccupiedEntryMut<'a, K, V, H>),
+    Occupied(RawOccupiedEntryMut<'a, K, V, S>),
     /// A vacant entry.
-    Vacant(RawVacantEntryMut<'a, K, V, H>),
+    Vacant(RawVacantEntryMut<'a, K, V, S>),
 }
 
 /// A view into an occupied entry in a `VecMap`.
 /// It is part of the [`RawEntryMut`] enum.
 ///
 /// [`RawEntryMut`]: enum.RawEntryMut.html
-pub struct RawOccupiedEntryMut<'a, K, V, H> {
+pub struct RawOccupiedEntryMut<'a, K, V, S> {
     idx: usize,
-    map: &'a mut Map<K, V, H>,
-}
-
-unsafe impl<K, V, H> Send for RawOccupiedEntryMut<'_, K, V, H>
-where
-    K: Send,
-    V: Send,
-{
-}
-unsafe impl<K, V, H> Sync for RawOccupiedEntryMut<'_, K, V, H>
-where
-    K: Sync,
-    V: Sync,
-{
+    map: &'a mut Map<K, V, S>,
 }
 
 /// A view into a vacant entry in a `VecMap`.
 /// It is part of the [`RawEntryMut`] enum.
 ///
 /// [`RawEntryMut`]: enum.RawEntryMut.html
-pub struct RawVacantEntryMut<'a, K, V, H> {
-    map: &'a mut Map<K, V, H>,
+pub struct RawVacantEntryMut<'a, K, V, S> {
+    map: &'a mut Map<K, V, S>,
 }
 
 /// A builder for computing where in a [`VecMap`] a key-value pair would be stored.
@@ -65,18 +52,18 @@ pub struct RawVacantEntryMut<'a, K, V, H> {
 /// See the [`VecMap::raw_entry`] docs for usage examples.
 ///
 /// [`VecMap::raw_entry`]: struct.VecMap.html#method.raw_entry
-pub struct RawEntryBuilder<'a, K, V, H> {
-    pub(crate) map: &'a Map<K, V, H>,
+pub struct RawEntryBuilder<'a, K, V, S> {
+    pub(crate) map: &'a Map<K, V, S>,
 }
 
-impl<'a, K, V, H> RawEntryBuilderMut<'a, K, V, H> {
+impl<'a, K, V, S> RawEntryBuilderMut<'a, K, V, S> {
     /// Creates a `RawEntryMut` from the given key.
     #[inline]
     #[allow(clippy::wrong_self_convention)]
-    pub fn from_key<Q: ?Sized>(self, k: &Q) -> RawEntryMut<'a, K, V, H>
+    pub fn from_key<Q>(self, k: &Q) -> RawEntryMut<'a, K, V, S>
     where
         K: Borrow<Q>,
-        Q: Eq,
+        Q: Eq + ?Sized,
     {
         self.from_key_hashed_nocheck(0, k)
     }
@@ -84,22 +71,22 @@ impl<'a, K, V, H> RawEntryBuilderMut<'a, K, V, H> {
     /// Creates a `RawEntryMut` from the given key and its hash.
     #[inline]
     #[allow(clippy::wrong_self_convention)]
-    pub fn from_key_hashed_nocheck<Q: ?Sized>(self, hash: u64, k: &Q) -> RawEntryMut<'a, K, V, H>
+    pub fn from_key_hashed_nocheck<Q>(self, hash: u64, k: &Q) -> RawEntryMut<'a, K, V, S>
     where
         K: Borrow<Q>,
-        Q: Eq,
+        Q: Eq + ?Sized,
     {
         self.from_hash(hash, |q| q.borrow().eq(k))
     }
 }
 
-impl<'a, K, V, H> RawEntryBuilderMut<'a, K, V, H> {
+impl<'a, K, V, S> RawEntryBuilderMut<'a, K, V, S> {
     /// Creates a `RawEntryMut` from the given hash.
     /// Note for the vec mapo hash has no effect it is only
     /// provided for convinience reasons
     #[inline]
     #[allow(clippy::wrong_self_convention)]
-    pub fn from_hash<F>(self, _hash: u64, is_match: F) -> RawEntryMut<'a, K, V, H>
+    pub fn from_hash<F>(self, _hash: u64, is_match: F) -> RawEntryMut<'a, K, V, S>
     where
         for<'b> F: FnMut(&'b K) -> bool,
     {
@@ -107,7 +94,7 @@ impl<'a, K, V, H> RawEntryBuilderMut<'a, K, V, H> {
     }
 
     #[inline]
-    fn search<F>(self, mut is_match: F) -> RawEntryMut<'a, K, V, H>
+    fn search<F>(self, mut is_match: F) -> RawEntryMut<'a, K, V, S>
     where
         for<'b> F: FnMut(&'b K) -> bool,
     {
@@ -120,14 +107,14 @@ impl<'a, K, V, H> RawEntryBuilderMut<'a, K, V, H> {
     }
 }
 
-impl<'a, K, V, H> RawEntryBuilder<'a, K, V, H> {
+impl<'a, K, V, S> RawEntryBuilder<'a, K, V, S> {
     /// Access an entry by key.
     #[inline]
     #[allow(clippy::wrong_self_convention)]
-    pub fn from_key<Q: ?Sized>(self, k: &Q) -> Option<(&'a K, &'a V)>
+    pub fn from_key<Q>(self, k: &Q) -> Option<(&'a K, &'a V)>
     where
         K: Borrow<Q>,
-        Q: Eq,
+        Q: Eq + ?Sized,
     {
         self.from_key_hashed_nocheck(0, k)
     }
@@ -136,10 +123,10 @@ impl<'a, K, V, H> RawEntryBuilder<'a, K, V, H> {
     /// Note hash has no effect for `VecMap`
     #[inline]
     #[allow(clippy::wrong_self_convention)]
-    pub fn from_key_hashed_nocheck<Q: ?Sized>(self, hash: u64, k: &Q) -> Option<(&'a K, &'a V)>
+    pub fn from_key_hashed_nocheck<Q>(self, hash: u64, k: &Q) -> Option<(&'a K, &'a V)>
     where
         K: Borrow<Q>,
-        Q: Eq,
+        Q: Eq + ?Sized,
     {
         self.from_hash(hash, |q| q.borrow().eq(k))
     }
@@ -170,10 +157,10 @@ impl<'a, K, V, H> RawEntryBuilder<'a, K, V, H> {
     }
 }
 
-impl<'a, K, V, H> RawEntryMut<'a, K, V, H>
+impl<'a, K, V, S> RawEntryMut<'a, K, V, S>
 where
     K: Eq + Hash + Clone,
-    H: Hasher + Default,
+    S: BuildHasher,
 {
     /// Sets the value of the entry, and returns a RawOccupiedEntryMut.
     ///
@@ -188,7 +175,7 @@ where
     /// assert_eq!(entry.remove_entry(), ("horseyland", 37));
     /// ```
     #[inline]
-    pub fn insert(self, key: K, value: V) -> RawOccupiedEntryMut<'a, K, V, H> {
+    pub fn insert(self, key: K, value: V) -> RawOccupiedEntryMut<'a, K, V, S> {
         match self {
             RawEntryMut::Occupied(mut entry) => {
                 entry.insert(value);
@@ -292,7 +279,7 @@ where
     }
 }
 
-impl<'a, K, V, H> RawOccupiedEntryMut<'a, K, V, H>
+impl<'a, K, V, S> RawOccupiedEntryMut<'a, K, V, S>
 where
     K: Clone,
 {
@@ -415,18 +402,14 @@ where
     /// Take the ownership of the key and value from the map.
     #[inline]
     pub fn remove_entry(self) -> (K, V) {
-        let n = unsafe { self.map.store.get_unchecked_mut(self.idx) };
-
-        let v = n.value.take().unwrap();
-
-        (n.key.clone(), v)
+        self.map.remove_index(self.idx)
     }
 }
 
-impl<'a, K, V, H> RawVacantEntryMut<'a, K, V, H>
+impl<'a, K, V, S> RawVacantEntryMut<'a, K, V, S>
 where
     K: Eq + Hash,
-    H: Hasher + Default,
+    S: BuildHasher,
 {
     /// Sets the value of the entry with the VacantEntry's key,
     /// and returns a mutable reference to it.
@@ -459,21 +442,21 @@ where
 
     /// Set the value of an entry with a custom hasher function.
     #[inline]
-    pub fn insert_with_hasher(
+    pub fn insert_with_hasher<F>(
         self,
         _hash: u64,
         key: K,
         value: V,
-        _hasher: H,
+        _hasher: F,
     ) -> (&'a mut K, &'a mut V)
     where
-        H: Fn(&K) -> u64,
+        F: Fn(&K) -> u64,
     {
         self.insert(key, value)
     }
 
     #[inline]
-    fn insert_entry(self, key: K, value: V) -> RawOccupiedEntryMut<'a, K, V, H> {
+    fn insert_entry(self, key: K, value: V) -> RawOccupiedEntryMut<'a, K, V, S> {
         let idx = self.map.store.len();
         self.map.insert(key, value);
         RawOccupiedEntryMut { idx, map: self.map }
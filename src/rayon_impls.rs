@@ -0,0 +1,53 @@
+//! Parallel iteration over `Map` with `rayon`, available with the `rayon`
+//! feature. Entries are split over the storage, without touching the tree.
+
+use super::*;
+use rayon::prelude::*;
+
+impl<K, V, S> Map<K, V, S> {
+    /// A parallel iterator visiting all key-value pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use rayon::prelude::*;
+    ///
+    /// let map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// assert_eq!(map.par_iter().map(|(_, value)| value).sum::<u32>(), 4950);
+    /// ```
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&K, &V)> + '_
+    where
+        K: Sync,
+        V: Sync,
+    {
+        self.store
+            .par_iter()
+            .filter_map(|node| node.value.as_ref().map(|value| (&node.key, value)))
+    }
+
+    /// A parallel iterator visiting all values mutably.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut map: Map<u32, u32> = (0..100u32).map(|x| (x, x)).collect();
+    ///
+    /// map.par_values_mut().for_each(|value| *value *= 2);
+    ///
+    /// assert_eq!(map[&50], 100);
+    /// ```
+    pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V> + '_
+    where
+        K: Send,
+        V: Send,
+    {
+        self.store
+            .par_iter_mut()
+            .filter_map(|node| node.value.as_mut())
+    }
+}
@@ -0,0 +1,124 @@
+//! `Serialize` and `Deserialize` for `Map`, available with the `serde`
+//! feature. Maps are serialized as maps, in insertion order, or sorted by
+//! key through [`serialize_sorted`](../struct.Map.html#method.serialize_sorted).
+
+use super::*;
+use core::marker::PhantomData;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+impl<K, V, S> Serialize for Map<K, V, S>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Serializes the entries of a `Map` sorted by key, leaving the map itself
+/// untouched.
+///
+/// This struct is created by the
+/// [`serialize_sorted`](./struct.Map.html#method.serialize_sorted) method on
+/// [`Map`](./struct.Map.html). See its documentation for more.
+pub struct SortedBy<'a, K, V, S = DefaultHashBuilder>(&'a Map<K, V, S>);
+
+impl<K, V, S> Map<K, V, S> {
+    /// Returns a wrapper serializing the map with its entries sorted by key,
+    /// rather than in insertion order, for reproducible output. The entries
+    /// are sorted anew on every serialization, which is `O(n log n)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ordnung::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.insert("b", 2);
+    /// map.insert("a", 1);
+    ///
+    /// let json = serde_json::to_string(&map.serialize_sorted()).unwrap();
+    ///
+    /// assert_eq!(json, r#"{"a":1,"b":2}"#);
+    /// assert!(map.keys().eq(&["b", "a"]));
+    /// ```
+    pub fn serialize_sorted(&self) -> SortedBy<'_, K, V, S> {
+        SortedBy(self)
+    }
+}
+
+impl<K, V, S> Serialize for SortedBy<'_, K, V, S>
+where
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let entries = sorted_entries(self.0);
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+
+        for (key, value) in entries {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+struct MapVisitor<K, V, S>(PhantomData<Map<K, V, S>>);
+
+impl<'de, K, V, S> Visitor<'de> for MapVisitor<K, V, S>
+where
+    K: Deserialize<'de> + Hash + Eq,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    type Value = Map<K, V, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        // Don't trust the hint too much, it comes from the input
+        let mut map = Map::default();
+
+        map.reserve(access.size_hint().unwrap_or(0).min(4096));
+
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+
+        Ok(map)
+    }
+}
+
+impl<'de, K, V, S> Deserialize<'de> for Map<K, V, S>
+where
+    K: Deserialize<'de> + Hash + Eq,
+    V: Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}